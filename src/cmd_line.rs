@@ -21,30 +21,89 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 //! This module defines the command-line arguments and behavior of `newdoc`.
 
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use bpaf::Bpaf;
 use serde::{Deserialize, Serialize};
 
 /// Generate pre-populated module files formatted with AsciiDoc that are used in Red Hat and Fedora documentation.
 #[derive(Clone, Debug, Bpaf)]
-#[bpaf(options, version)]
+#[bpaf(options, version, guard(at_least_one_file, SOME_FILES))]
 pub struct Cli {
-    #[bpaf(
-        external,
-        group_help("Generate or validate files:"),
-        guard(at_least_one_file, SOME_FILES)
-    )]
+    #[bpaf(external, group_help("Generate or validate files:"))]
     pub action: Action,
 
     #[bpaf(external, group_help("Common options:"))]
     pub common_options: CommonOptions,
+
+    // Commands must come last, after all the named options.
+    #[bpaf(external, optional, group_help("Commands:"))]
+    pub command: Option<Command>,
+}
+
+// Commands that work with an outline or with existing documents rather than with single new files.
+#[derive(Clone, Debug, Bpaf)]
+pub enum Command {
+    /// Compare an outline with the files on the disk and show what `apply` would change
+    #[bpaf(command, adjacent)]
+    Plan {
+        /// The outline of assemblies and modules in the YAML format
+        #[bpaf(positional("OUTLINE"))]
+        outline: PathBuf,
+    },
+    /// Create the files that are missing from an outline and add their includes to assemblies
+    #[bpaf(command, adjacent)]
+    Apply {
+        /// The outline of assemblies and modules in the YAML format
+        #[bpaf(positional("OUTLINE"))]
+        outline: PathBuf,
+    },
+    /// Split a monolithic AsciiDoc document into modules and turn it into an assembly
+    #[bpaf(command, adjacent)]
+    Split {
+        /// Ask for the content type of each module instead of inferring it
+        #[bpaf(short, long)]
+        interactive: bool,
+        /// The AsciiDoc file to split
+        #[bpaf(positional("FILE"))]
+        file: PathBuf,
+    },
+    /// Convert a Markdown, DocBook, or HTML document into modules and an assembly
+    #[bpaf(command, adjacent)]
+    Import {
+        /// Ask for the content type of each module instead of inferring it
+        #[bpaf(short, long)]
+        interactive: bool,
+        /// The file to convert, in a format recognized by its extension
+        #[bpaf(positional("FILE"))]
+        file: PathBuf,
+    },
 }
 
+impl Command {
+    /// The name of the command as the user types it.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Plan { .. } => "plan",
+            Self::Apply { .. } => "apply",
+            Self::Split { .. } => "split",
+            Self::Import { .. } => "import",
+        }
+    }
+}
+
+// The command-line flags are independent switches, so the bools are the natural representation.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, Debug, Bpaf)]
 pub struct CommonOptions {
     /// Save the generated files in this directory
-    #[bpaf(short('T'), long, argument("DIRECTORY"), fallback(".".into()))]
-    pub target_dir: PathBuf,
+    #[bpaf(short('T'), long, argument("DIRECTORY"))]
+    pub target_dir: Option<PathBuf>,
+
+    /// Replace the {topic} placeholder in the configured target directories
+    #[bpaf(long, argument("TOPIC"))]
+    pub topic: Option<String>,
 
     #[bpaf(external, optional)]
     pub comments: Option<Comments>,
@@ -64,35 +123,72 @@ pub struct CommonOptions {
     #[bpaf(external, optional)]
     pub simplified: Option<Simplified>,
 
+    /// What to do when a generated file already exists: ask, never, always, or backup
+    #[bpaf(long, argument("POLICY"))]
+    pub overwrite: Option<Overwrite>,
+
+    /// How to treat non-ASCII characters in IDs: legacy or transliterate
+    #[bpaf(long, argument("STYLE"))]
+    pub id_style: Option<IdStyle>,
+
+    /// What to do when an ID or a file name already exists in the repository: error, suffix, or off
+    #[bpaf(long, argument("POLICY"))]
+    pub id_collision: Option<IdCollision>,
+
+    /// What to do when a title results in an empty or too short ID: error, transliterate, or hash
+    #[bpaf(long, argument("SCHEME"))]
+    pub id_fallback: Option<IdFallback>,
+
+    /// Shorten longer IDs and file names at a word boundary
+    #[bpaf(long, argument("LENGTH"))]
+    pub max_id_length: Option<usize>,
+
+    /// Create the target directory and its parents if they don't exist
+    #[bpaf(long)]
+    pub create_dirs: bool,
+
+    /// Open the generated files in your editor
+    #[bpaf(long)]
+    pub edit: bool,
+
+    /// Stage the written files in their Git repository
+    #[bpaf(long)]
+    pub git_add: bool,
+
+    /// Stage the written files and commit them with this message
+    #[bpaf(long, argument("MESSAGE"))]
+    pub git_commit: Option<String>,
+
+    /// Show the files that newdoc would generate, without writing anything
+    #[bpaf(long)]
+    pub dry_run: bool,
+
+    /// With --dry-run, also print the generated text of each file
+    #[bpaf(long)]
+    pub show_text: bool,
+
+    /// Print the generated text to the standard output instead of writing files
+    #[bpaf(long)]
+    pub stdout: bool,
+
+    /// The format of the report after the run: text or json
+    #[bpaf(long, argument("FORMAT"))]
+    pub output: Option<OutputFormat>,
+
     #[bpaf(external, fallback(Verbosity::default()))]
     pub verbosity: Verbosity,
 }
 
 #[derive(Clone, Debug, Bpaf)]
 pub struct Action {
-    /// Create an assembly file
-    #[bpaf(short, long, argument("TITLE"))]
-    pub assembly: Vec<String>,
-
-    /// Create a concept module
-    #[bpaf(short, long, argument("TITLE"))]
-    pub concept: Vec<String>,
-
-    /// Create a procedure module
-    #[bpaf(short, long, argument("TITLE"))]
-    pub procedure: Vec<String>,
+    /// The files to generate, in the order in which they appear on the command line
+    #[bpaf(external(action_item), many)]
+    pub items: Vec<ActionItem>,
 
-    /// Create a reference module
-    #[bpaf(short, long, argument("TITLE"))]
-    pub reference: Vec<String>,
-
-    /// Create a snippet file
-    #[bpaf(short, long, argument("TITLE"))]
-    pub snippet: Vec<String>,
-
-    /// Create an assembly that includes the other specified modules
-    #[bpaf(short, long, argument("TITLE"))]
-    pub include_in: Option<String>,
+    /// List this module title or existing file first in the populated assembly.
+    /// Repeat the option to set the order of several includes
+    #[bpaf(long, argument("TITLE"))]
+    pub order: Vec<String>,
 
     /// REMOVED: Validate (lint) an existing module or assembly file
     /// The option is hidden, has no effect, and exists only for compatibility
@@ -101,6 +197,59 @@ pub struct Action {
     pub validate: Vec<PathBuf>,
 }
 
+/// A single file that the user requested on the command line.
+/// The items are parsed as one list so that newdoc knows their order,
+/// which determines the populated assembly that each module belongs to.
+#[derive(Clone, Debug, Bpaf)]
+pub enum ActionItem {
+    Assembly {
+        /// Create an assembly file
+        #[bpaf(short, long, argument("TITLE"))]
+        assembly: String,
+    },
+    Concept {
+        /// Create a concept module
+        #[bpaf(short, long, argument("TITLE"))]
+        concept: String,
+    },
+    Procedure {
+        /// Create a procedure module
+        #[bpaf(short, long, argument("TITLE"))]
+        procedure: String,
+    },
+    Reference {
+        /// Create a reference module
+        #[bpaf(short, long, argument("TITLE"))]
+        reference: String,
+    },
+    Snippet {
+        /// Create a snippet file
+        #[bpaf(short, long, argument("TITLE"))]
+        snippet: String,
+    },
+    IncludeIn {
+        /// Create an assembly that includes the other specified modules.
+        /// Repeat the option to create several assemblies, each including the modules that follow it
+        #[bpaf(short, long, argument("TITLE"))]
+        include_in: String,
+    },
+    IncludeExisting {
+        /// Include an existing module or assembly file in the populated assembly
+        #[bpaf(long, argument("FILE"))]
+        include_existing: PathBuf,
+    },
+    Id {
+        /// Use this ID for the preceding title instead of deriving it from the title
+        #[bpaf(long, argument("ID"))]
+        id: String,
+    },
+    FileName {
+        /// Use this file name for the preceding title instead of deriving it from the ID
+        #[bpaf(long, argument("FILE_NAME"))]
+        file_name: String,
+    },
+}
+
 /// The verbosity level set on the command line.
 /// The default option is invisible as a command-line argument.
 #[derive(Clone, Copy, Debug, Bpaf, Default, PartialEq, Serialize, Deserialize)]
@@ -117,6 +266,141 @@ pub enum Verbosity {
     Default,
 }
 
+/// The policy for generated files that already exist at the target path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Overwrite {
+    /// Ask before overwriting each file. Without a terminal, preserve the file.
+    #[default]
+    Ask,
+    /// Always preserve the existing file
+    Never,
+    /// Always overwrite the existing file
+    Always,
+    /// Rename the existing file with a backup suffix and write the new file
+    Backup,
+}
+
+impl FromStr for Overwrite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ask" => Ok(Self::Ask),
+            "never" => Ok(Self::Never),
+            "always" => Ok(Self::Always),
+            "backup" => Ok(Self::Backup),
+            _ => Err(format!(
+                "Unknown overwrite policy `{s}`. Use ask, never, always, or backup."
+            )),
+        }
+    }
+}
+
+/// How newdoc converts non-ASCII characters in titles to IDs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdStyle {
+    /// Replace each non-ASCII character with a dash, as in previous releases
+    #[default]
+    Legacy,
+    /// Transliterate the characters to ASCII, such as `ß` to `ss`
+    Transliterate,
+}
+
+impl FromStr for IdStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "legacy" => Ok(Self::Legacy),
+            "transliterate" => Ok(Self::Transliterate),
+            _ => Err(format!(
+                "Unknown ID style `{s}`. Use legacy or transliterate."
+            )),
+        }
+    }
+}
+
+/// What newdoc does when a generated ID or file name already exists in the repository.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdCollision {
+    /// Report the collision and write no files
+    #[default]
+    Error,
+    /// Add a numbered suffix to the ID, such as `-2`, until it's unique
+    Suffix,
+    /// Skip the check and the scan of the repository
+    #[serde(alias = "ignore")]
+    Off,
+}
+
+impl FromStr for IdCollision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(Self::Error),
+            "suffix" => Ok(Self::Suffix),
+            "off" | "ignore" => Ok(Self::Off),
+            _ => Err(format!(
+                "Unknown ID collision policy `{s}`. Use error, suffix, or off."
+            )),
+        }
+    }
+}
+
+/// How newdoc replaces a degenerate ID, such as an empty ID from a title in Chinese.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdFallback {
+    /// Report the degenerate ID and write no files
+    #[default]
+    Error,
+    /// Romanize the title, such as `安装` to `an-zhuang`
+    Transliterate,
+    /// Use a hash of the title, such as `id-1a2b3c4d`
+    Hash,
+}
+
+impl FromStr for IdFallback {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(Self::Error),
+            "transliterate" => Ok(Self::Transliterate),
+            "hash" => Ok(Self::Hash),
+            _ => Err(format!(
+                "Unknown ID fallback `{s}`. Use error, transliterate, or hash."
+            )),
+        }
+    }
+}
+
+/// The format of the report about the generated files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable log messages
+    #[default]
+    Text,
+    /// A JSON array with a record for each generated file, printed to the standard output
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("Unknown output format `{s}`. Use text or json.")),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Bpaf, Default, PartialEq)]
 pub enum Comments {
     /// Generate the file without any comments. (Default)
@@ -183,19 +467,14 @@ pub enum AnchorPrefixes {
     NoAnchorPrefixes,
 }
 
-/// Check that the current command generates or validates at least one file.
-fn at_least_one_file(action: &Action) -> bool {
-    !action.assembly.is_empty()
-        || !action.concept.is_empty()
-        || !action.procedure.is_empty()
-        || !action.reference.is_empty()
-        || !action.snippet.is_empty()
-        || !action.validate.is_empty()
-        || action.include_in.is_some()
+/// Check that the current command generates or validates at least one file,
+/// or that it runs an outline command.
+fn at_least_one_file(cli: &Cli) -> bool {
+    cli.command.is_some() || !cli.action.items.is_empty() || !cli.action.validate.is_empty()
 }
 
 /// The error message if the command does not generate or validate files.
-const SOME_FILES: &str = "Specify at least one file to generate.";
+const SOME_FILES: &str = "Specify at least one file to generate, or a command.";
 
 /// Get command-line arguments as the `Cli` struct.
 #[must_use]
@@ -0,0 +1,547 @@
+/*
+newdoc: Generate pre-populated documentation modules formatted with AsciiDoc.
+Copyright (C) 2026  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! # `collisions.rs`
+//!
+//! This module finds the IDs and the file names that already exist in the repository,
+//! so that newdoc doesn't generate a duplicate. A duplicate ID otherwise only surfaces
+//! as a broken build later.
+//!
+//! newdoc scans the AsciiDoc files from the root of the Git repository,
+//! or only the target directory itself outside of a repository.
+//!
+//! The IDs compare in the form that the files declare them. A module ID ends with
+//! the `_{context}` suffix, which the including assembly sets to its own ID, so a module
+//! can share the ID of an assembly without a collision.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{bail, Result};
+use regex::Regex;
+
+use crate::cmd_line::IdCollision;
+use crate::module::{absolute_path, ContentType, Input};
+use crate::{Options, REGEX_ERROR};
+
+/// The `[id=...]` attribute anywhere in an attribute list, with double, single, or no quotes,
+/// and the `[[...]]` and `[#...]` forms of an ID declaration
+const DECLARATION: &str = r#"\[(?:[^\[\]\n]*,\s*)?id=(?:"([^"]+)"|'([^']+)'|([^\s,"'\]]+))[^\]\n]*\]|\[\[([^\[\],\s]+)(?:,[^\]]*)?\]\]|\[#([^\[\]\s.%,]+)[^\]]*\]"#;
+
+/// The IDs and the file names in the repository, and the files where they appear.
+#[derive(Debug, Default)]
+pub struct Existing {
+    /// The declared IDs, including the `_{context}` suffix if they have one
+    ids: HashMap<String, Vec<PathBuf>>,
+    /// The names of the AsciiDoc files
+    file_names: HashMap<String, Vec<PathBuf>>,
+    /// The files that newdoc generates in this run, rather than found in the repository
+    generated: HashSet<PathBuf>,
+}
+
+impl Existing {
+    /// Scan the AsciiDoc files in the repository for IDs and file names.
+    /// Outside of a Git repository, scan only the target directory itself,
+    /// because it might be a large directory such as the home directory.
+    /// With the `id_collision` option set to `off`, skip the scan.
+    pub fn scan(options: &Options) -> Self {
+        if options.id_collision == IdCollision::Off {
+            return Self::default();
+        }
+
+        let (root, recursive) = match &options.git_root {
+            Some(git_root) => (git_root, true),
+            None => (&options.target_dir, false),
+        };
+        let mut existing = Self::default();
+
+        // The target directory might not exist yet with the --create-dirs option.
+        if let Ok(root) = root.canonicalize() {
+            let declaration = Regex::new(DECLARATION).expect(REGEX_ERROR);
+            existing.scan_dir(&root, &declaration, recursive);
+        }
+
+        log::debug!(
+            "Found {} IDs and {} file names in the repository.",
+            existing.ids.len(),
+            existing.file_names.len()
+        );
+
+        existing
+    }
+
+    /// Record the IDs and the file names in this directory, and with `recursive`,
+    /// in its subdirectories. Skips hidden directories, such as `.git`,
+    /// and doesn't follow symbolic links. Entries that newdoc can't read
+    /// only produce a warning, because they're unrelated to the new files.
+    fn scan_dir(&mut self, dir: &Path, declaration: &Regex, recursive: bool) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(error) => {
+                log::warn!(
+                    "Skipping the `{}` directory in the collision check: {error}",
+                    dir.display()
+                );
+                return;
+            }
+        };
+
+        for entry in entries {
+            let entry =
+                entry.and_then(|entry| entry.file_type().map(|file_type| (entry, file_type)));
+            let (entry, file_type) = match entry {
+                Ok(result) => result,
+                Err(error) => {
+                    log::warn!(
+                        "Skipping an entry in the `{}` directory in the collision check: {error}",
+                        dir.display()
+                    );
+                    continue;
+                }
+            };
+            let path = entry.path();
+            let file_name = entry.file_name().to_string_lossy().to_string();
+
+            if file_name.starts_with('.') {
+                continue;
+            }
+
+            if file_type.is_dir() {
+                if recursive {
+                    self.scan_dir(&path, declaration, recursive);
+                }
+            } else if file_type.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| extension == "adoc")
+            {
+                // Files that aren't valid UTF-8 can't declare an ID that newdoc generates.
+                if let Ok(text) = fs::read_to_string(&path) {
+                    for captures in declaration.captures_iter(&text) {
+                        if let Some(id) = captures.iter().skip(1).flatten().next() {
+                            self.ids
+                                .entry(id.as_str().to_string())
+                                .or_default()
+                                .push(path.clone());
+                        }
+                    }
+                } else {
+                    log::debug!("Skipping a file that isn't UTF-8: {}", path.display());
+                }
+
+                self.file_names.entry(file_name).or_default().push(path);
+            }
+        }
+    }
+
+    /// Forget the IDs and the file name of a file that the new files replace,
+    /// such as a document that newdoc splits into modules.
+    pub fn forget(&mut self, path: &Path) {
+        let Some(path) = absolute_path(path) else {
+            return;
+        };
+        for paths in self.ids.values_mut().chain(self.file_names.values_mut()) {
+            paths.retain(|existing| *existing != path);
+        }
+    }
+
+    /// Check the ID and the file name of the new file against the existing content.
+    /// Depending on the `id_collision` option, either report the collision,
+    /// or add a suffix to the ID until it's unique.
+    ///
+    /// A file that already exists at the target path doesn't count as a collision.
+    /// The overwrite policy decides about it.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ID or the file name collides, and either the `id_collision` option
+    /// is `error`, or the colliding ID or file name was set explicitly.
+    pub fn resolve(&mut self, mut input: Input, options: &Options) -> Result<Input> {
+        if options.id_collision == IdCollision::Off {
+            return Ok(input);
+        }
+
+        let original = input.anchor();
+        let mut suffix = 1;
+
+        loop {
+            let (ids, file_names) = self.collisions(&input);
+
+            if ids.is_empty() && file_names.is_empty() {
+                break;
+            }
+
+            // newdoc never changes an ID or a file name that the user or the converted
+            // content specified. The file name follows the ID, so an explicit ID fixes both.
+            let fixed = (!ids.is_empty() && input.explicit_id.is_some())
+                || (!file_names.is_empty()
+                    && (input.explicit_id.is_some() || input.explicit_file_name.is_some()));
+            let hint = if fixed {
+                "Change the ID or the file name that you set for it."
+            } else {
+                "Change the title, or add the `--id-collision suffix` option \
+                to make the ID unique with a numbered suffix."
+            };
+
+            if fixed || options.id_collision == IdCollision::Error {
+                bail!(
+                    "The {} `{}` collides with existing content:\n{}\n{hint}",
+                    input.mod_type,
+                    input.title,
+                    ids.into_iter()
+                        .chain(file_names)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                );
+            }
+
+            suffix += 1;
+            input.id_suffix = Some(suffix);
+        }
+
+        if input.id_suffix.is_some() {
+            log::info!(
+                "‣ ID changed to avoid a collision: {original} → {}",
+                input.anchor()
+            );
+        }
+
+        // Later files in the same run must not collide with this one.
+        let path = target_path(&input);
+        self.generated.insert(path.clone());
+        if let Some(id) = declared_id(&input) {
+            self.ids.entry(id).or_default().push(path.clone());
+        }
+        self.file_names
+            .entry(input.file_name())
+            .or_default()
+            .push(path);
+
+        Ok(input)
+    }
+
+    /// Describe each place where the ID and where the file name of the new file already exist.
+    fn collisions(&self, input: &Input) -> (Vec<String>, Vec<String>) {
+        let path = target_path(input);
+        let elsewhere = |paths: Option<&Vec<PathBuf>>| -> Vec<PathBuf> {
+            paths
+                .into_iter()
+                .flatten()
+                .filter(|existing| **existing != path)
+                .cloned()
+                .collect()
+        };
+
+        let file_name = input.file_name();
+
+        let ids = declared_id(input)
+            .map(|id| {
+                elsewhere(self.ids.get(&id))
+                    .into_iter()
+                    .map(|existing| self.describe(&format!("`{id}` ID"), &existing))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let file_names = elsewhere(self.file_names.get(&file_name))
+            .into_iter()
+            .map(|existing| self.describe(&format!("`{file_name}` file"), &existing))
+            .collect();
+
+        (ids, file_names)
+    }
+
+    /// Describe where the colliding ID or file name is: in the repository,
+    /// or in another file that newdoc generates in this run.
+    fn describe(&self, what: &str, existing: &Path) -> String {
+        if self.generated.contains(existing) {
+            format!(
+                "  The {what} collides with {}, which newdoc generates in this run",
+                existing.display()
+            )
+        } else {
+            format!("  The {what} exists in {}", existing.display())
+        }
+    }
+}
+
+/// The ID as the new file declares it. Modules add the `_{context}` suffix,
+/// while assemblies declare the plain ID at the top level. Snippets have no ID.
+fn declared_id(input: &Input) -> Option<String> {
+    let anchor = input.anchor();
+
+    match input.mod_type {
+        ContentType::Snippet => None,
+        ContentType::Assembly => Some(anchor),
+        _ if input.options.simplified => Some(anchor),
+        _ => Some(format!("{anchor}_{{context}}")),
+    }
+}
+
+/// The absolute path where newdoc saves the new file, comparable with the scanned paths.
+fn target_path(input: &Input) -> PathBuf {
+    let path = input.options.target_dir.join(input.file_name());
+    absolute_path(&path).unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a repository with a module that declares the `installing-the-product` ID.
+    fn repository() -> tempfile::TempDir {
+        let temp = tempfile::tempdir().expect("Failed to create a temporary directory");
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("modules/other")).expect("Failed to create the directory");
+        fs::write(
+            dir.join("modules/other/con_about-the-product.adoc"),
+            "[id=\"installing-the-product_{context}\"]\n= About the product\n",
+        )
+        .expect("Failed to write the file");
+
+        temp
+    }
+
+    fn options(dir: &Path, id_collision: IdCollision) -> Options {
+        Options {
+            git_root: Some(dir.to_path_buf()),
+            target_dir: dir.join("modules"),
+            id_collision,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn report_collisions() {
+        let dir = repository();
+        let options = options(dir.path(), IdCollision::Error);
+        let mut existing = Existing::scan(&options);
+
+        let input = Input::new(ContentType::Procedure, "Installing the product", &options);
+        let error = existing
+            .resolve(input, &options)
+            .expect_err("The ID collision wasn't detected");
+        assert!(error
+            .to_string()
+            .contains("`installing-the-product_{context}` ID"));
+
+        let input = Input::new(ContentType::Concept, "About the product", &options);
+        let error = existing
+            .resolve(input, &options)
+            .expect_err("The file name collision wasn't detected");
+        assert!(error
+            .to_string()
+            .contains("`con_about-the-product.adoc` file"));
+
+        let input = Input::new(ContentType::Concept, "Product overview", &options);
+        assert!(existing.resolve(input, &options).is_ok());
+    }
+
+    #[test]
+    fn add_suffix() {
+        let dir = repository();
+        let options = options(dir.path(), IdCollision::Suffix);
+        let mut existing = Existing::scan(&options);
+
+        let input = Input::new(ContentType::Procedure, "Installing the product", &options);
+        let input = existing
+            .resolve(input, &options)
+            .expect("Failed to resolve the collision");
+        assert_eq!("installing-the-product-2", input.anchor());
+        assert_eq!("proc_installing-the-product-2.adoc", input.file_name());
+
+        // The new file counts for the rest of the run.
+        let input = Input::new(ContentType::Concept, "Installing the product", &options);
+        let input = existing
+            .resolve(input, &options)
+            .expect("Failed to resolve the collision");
+        assert_eq!("installing-the-product-3", input.anchor());
+    }
+
+    #[test]
+    fn explicit_file_name_collision() {
+        let dir = repository();
+        let options = options(dir.path(), IdCollision::Suffix);
+        let mut existing = Existing::scan(&options);
+
+        // A suffix can't change an explicit file name, so newdoc reports the collision.
+        let input = Input::new(ContentType::Concept, "Product overview", &options)
+            .with_file_name("con_about-the-product");
+        let error = existing
+            .resolve(input, &options)
+            .expect_err("The file name collision wasn't detected");
+        assert!(error
+            .to_string()
+            .contains("`con_about-the-product.adoc` file"));
+
+        // The file name follows an explicit ID, so it can't change either.
+        let input = Input::new(ContentType::Concept, "Product overview", &options)
+            .with_id("about-the-product");
+        assert!(existing.resolve(input, &options).is_err());
+
+        // With an explicit file name, the derived ID can still get a suffix.
+        let input = Input::new(ContentType::Procedure, "Installing the product", &options)
+            .with_file_name("proc_setup");
+        let input = existing
+            .resolve(input, &options)
+            .expect("Failed to resolve the collision");
+        assert_eq!("installing-the-product-2", input.anchor());
+        assert_eq!("proc_setup.adoc", input.file_name());
+    }
+
+    #[test]
+    fn assembly_and_module_with_one_title() {
+        let dir = repository();
+        let options = options(dir.path(), IdCollision::Error);
+        let mut existing = Existing::scan(&options);
+
+        // The module ID has the `_{context}` suffix, which the assembly sets to its own ID.
+        let input = Input::new(ContentType::Procedure, "Setting up the product", &options);
+        assert!(existing.resolve(input, &options).is_ok());
+        let input = Input::new(ContentType::Assembly, "Setting up the product", &options);
+        assert!(existing.resolve(input, &options).is_ok());
+
+        // Simplified modules and assemblies declare the same ID.
+        let options = Options {
+            simplified: true,
+            ..options
+        };
+        let input = Input::new(ContentType::Procedure, "Removing the product", &options);
+        assert!(existing.resolve(input, &options).is_ok());
+        let input = Input::new(ContentType::Assembly, "Removing the product", &options);
+        assert!(existing.resolve(input, &options).is_err());
+    }
+
+    #[test]
+    fn anchor_forms() {
+        let dir = repository();
+        fs::write(
+            dir.path().join("modules/other/ref_anchors.adoc"),
+            "[[double-brackets]]\n= Double brackets\n\n\
+            [[with-reftext,Reference text]]\n== With reference text\n\n\
+            [#shorthand.role]\n== Shorthand\n\n\
+            [id='single-quotes']\n== Single quotes\n\n\
+            [id=\"with-role\",role=\"_abstract\"]\n== With role\n\n\
+            [role=\"a,b\", id=unquoted]\n== Unquoted\n",
+        )
+        .expect("Failed to write the file");
+        let options = Options {
+            simplified: true,
+            ..options(dir.path(), IdCollision::Error)
+        };
+        let mut existing = Existing::scan(&options);
+
+        for title in [
+            "Double brackets",
+            "With reftext",
+            "Shorthand",
+            "Single quotes",
+            "With role",
+            "Unquoted",
+        ] {
+            let input = Input::new(ContentType::Concept, title, &options);
+            let error = existing
+                .resolve(input, &options)
+                .expect_err("The ID collision wasn't detected");
+            assert!(error.to_string().contains(" ID exists in "));
+        }
+    }
+
+    #[test]
+    fn existing_target_is_not_a_collision() {
+        let dir = repository();
+        let options = Options {
+            target_dir: dir.path().join("modules/other"),
+            ..options(dir.path(), IdCollision::Error)
+        };
+        let mut existing = Existing::scan(&options);
+
+        let input = Input::new(ContentType::Concept, "About the product", &options);
+        assert!(existing.resolve(input, &options).is_ok());
+    }
+
+    #[test]
+    fn scan_only_the_target_directory_outside_git() {
+        let dir = repository();
+        fs::write(
+            dir.path().join("ref_top-level.adoc"),
+            "[id=\"top-level_{context}\"]\n= Top level\n",
+        )
+        .expect("Failed to write the file");
+        let options = Options {
+            git_root: None,
+            target_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let mut existing = Existing::scan(&options);
+
+        let input = Input::new(ContentType::Concept, "Top level", &options);
+        assert!(existing.resolve(input, &options).is_err());
+
+        // The module in the subdirectory is out of the scan.
+        let input = Input::new(ContentType::Procedure, "Installing the product", &options);
+        assert!(existing.resolve(input, &options).is_ok());
+    }
+
+    #[test]
+    fn skip_unreadable_directories() {
+        let dir = repository();
+        let mut existing = Existing::default();
+        let declaration = Regex::new(r#"\[id="([^"]+)"\]"#).expect(REGEX_ERROR);
+
+        // A directory that newdoc can't list doesn't stop the scan.
+        existing.scan_dir(&dir.path().join("missing"), &declaration, true);
+        existing.scan_dir(dir.path(), &declaration, true);
+
+        assert!(existing
+            .ids
+            .contains_key("installing-the-product_{context}"));
+    }
+
+    #[test]
+    fn collision_within_the_run() {
+        let dir = repository();
+        let options = options(dir.path(), IdCollision::Error);
+        let mut existing = Existing::scan(&options);
+
+        let input = Input::new(ContentType::Concept, "Product overview", &options);
+        assert!(existing.resolve(input, &options).is_ok());
+        let input = Input::new(ContentType::Concept, "Product overview", &options)
+            .with_file_name("con_overview");
+        let error = existing
+            .resolve(input, &options)
+            .expect_err("The ID collision wasn't detected");
+        let message = error.to_string();
+        assert!(message.contains("The `product-overview_{context}` ID collides with "));
+        assert!(message.contains("con_product-overview.adoc, which newdoc generates in this run"));
+    }
+
+    #[test]
+    fn collision_check_off() {
+        let dir = repository();
+        let options = options(dir.path(), IdCollision::Off);
+        let mut existing = Existing::scan(&options);
+        assert!(existing.ids.is_empty() && existing.file_names.is_empty());
+
+        let input = Input::new(ContentType::Procedure, "Installing the product", &options);
+        let input = existing
+            .resolve(input, &options)
+            .expect("The collision check wasn't off");
+        assert_eq!("installing-the-product", input.anchor());
+    }
+}
@@ -21,9 +21,12 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 //! This module defines the global options merged from the command line,
 //! the configuration files, and the defaults.
 
+use std::env;
+use std::ffi::OsString;
+use std::fs;
 use std::path::{Path, PathBuf};
 
-use color_eyre::eyre::{Result, WrapErr};
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
 use directories::ProjectDirs;
 use figment::{
     providers::{Format, Serialized, Toml},
@@ -32,13 +35,21 @@ use figment::{
 use serde::{Deserialize, Serialize};
 
 use crate::cmd_line::{
-    AnchorPrefixes, Cli, Comments, Examples, FilePrefixes, Metadata, Simplified, Verbosity,
+    AnchorPrefixes, Cli, Comments, Examples, FilePrefixes, IdCollision, IdFallback, IdStyle,
+    Metadata, OutputFormat, Overwrite, Simplified, Verbosity,
 };
 
+use crate::module::{absolute_path, ContentType};
+
+/// The placeholder for the topic in the per-type target directories.
+const TOPIC_PLACEHOLDER: &str = "{topic}";
+
 const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 
 /// This struct stores options based on the command-line arguments,
 /// and is passed to various functions across the program.
+// The options are independent switches, so the bools are the natural representation.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Options {
     pub comments: bool,
@@ -48,10 +59,95 @@ pub struct Options {
     pub metadata: bool,
     pub target_dir: PathBuf,
     pub simplified: bool,
+    pub overwrite: Overwrite,
+    pub create_dirs: bool,
+    pub id_style: IdStyle,
+    pub id_collision: IdCollision,
+    pub id_fallback: IdFallback,
+    pub min_id_length: usize,
+    pub max_id_length: Option<usize>,
+    pub id_substitutions: Vec<(String, String)>,
+    pub semantic_roles: Vec<String>,
+    pub target_dirs: TargetDirs,
+    pub editor: Option<String>,
     pub verbosity: Verbosity,
+    #[serde(skip)]
+    pub git_root: Option<PathBuf>,
+    #[serde(skip)]
+    pub topic: Option<String>,
+    #[serde(skip)]
+    pub edit: bool,
+    #[serde(skip)]
+    pub git_add: bool,
+    #[serde(skip)]
+    pub git_commit: Option<String>,
+    #[serde(skip)]
+    pub dry_run: bool,
+    #[serde(skip)]
+    pub show_text: bool,
+    #[serde(skip)]
+    pub stdout: bool,
+    #[serde(skip)]
+    pub output: OutputFormat,
+}
+
+/// The target directories for each content type, relative to the root of the Git repository.
+/// A directory can contain the `{topic}` placeholder, which the `--topic` option replaces.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TargetDirs {
+    pub assembly: Option<String>,
+    pub concept: Option<String>,
+    pub procedure: Option<String>,
+    pub reference: Option<String>,
+    pub snippet: Option<String>,
+}
+
+impl TargetDirs {
+    /// The configured directory for this content type, if any.
+    fn get(&self, mod_type: ContentType) -> Option<&str> {
+        match mod_type {
+            ContentType::Assembly => self.assembly.as_deref(),
+            ContentType::Concept => self.concept.as_deref(),
+            ContentType::Procedure => self.procedure.as_deref(),
+            ContentType::Reference => self.reference.as_deref(),
+            ContentType::Snippet => self.snippet.as_deref(),
+        }
+    }
 }
 
 impl Options {
+    /// The directory where newdoc saves files of this content type.
+    /// A directory configured for the content type applies relative to the Git repository root.
+    /// Otherwise, newdoc uses the general target directory.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the configured directory contains the `{topic}` placeholder, but the topic isn't set.
+    pub fn target_dir_for(&self, mod_type: ContentType) -> Result<PathBuf> {
+        let Some(dir) = self.target_dirs.get(mod_type) else {
+            return Ok(self.target_dir.clone());
+        };
+        // `merge_configs` warns once that the directories don't apply outside Git.
+        let Some(git_root) = &self.git_root else {
+            return Ok(self.target_dir.clone());
+        };
+
+        let dir = if dir.contains(TOPIC_PLACEHOLDER) {
+            let Some(topic) = &self.topic else {
+                bail!(
+                    "The target directory for {mod_type} files, `{dir}`, needs a topic. \
+                    Add the --topic option."
+                );
+            };
+            dir.replace(TOPIC_PLACEHOLDER, topic)
+        } else {
+            dir.to_string()
+        };
+
+        Ok(git_root.join(dir))
+    }
+
     /// Update the values in this instance from the command line, but only in cases
     /// where the command line's values are specified.
     /// Where the command line options are missing, preserve the value in self.
@@ -116,6 +212,24 @@ impl Options {
             }
             None => { /* Keep the existing value. */ }
         }
+        if let Some(overwrite) = cli.common_options.overwrite {
+            self.overwrite = overwrite;
+        }
+        if let Some(id_style) = cli.common_options.id_style {
+            self.id_style = id_style;
+        }
+        if let Some(id_collision) = cli.common_options.id_collision {
+            self.id_collision = id_collision;
+        }
+        if let Some(id_fallback) = cli.common_options.id_fallback {
+            self.id_fallback = id_fallback;
+        }
+        if let Some(max_id_length) = cli.common_options.max_id_length {
+            self.max_id_length = Some(max_id_length);
+        }
+        if cli.common_options.create_dirs {
+            self.create_dirs = true;
+        }
         // TODO: Because the verbosity field isn't optional on the CLI, but rather
         // defaults to the `Default` value, the CLI always overrides the config files,
         // even though the config files recognize the option in theory.
@@ -133,7 +247,20 @@ impl Options {
 
         // These options only exist on the command line, not in config files.
         // Always use the value from CLI arguments.
-        self.target_dir.clone_from(&cli.common_options.target_dir);
+        // A target directory on the command line applies to all content types.
+        if let Some(target_dir) = &cli.common_options.target_dir {
+            self.target_dir.clone_from(target_dir);
+            self.target_dirs = TargetDirs::default();
+        }
+        self.topic.clone_from(&cli.common_options.topic);
+        self.edit = cli.common_options.edit;
+        self.git_commit.clone_from(&cli.common_options.git_commit);
+        // Committing the files requires staging them first.
+        self.git_add = cli.common_options.git_add || self.git_commit.is_some();
+        self.dry_run = cli.common_options.dry_run;
+        self.show_text = cli.common_options.show_text;
+        self.stdout = cli.common_options.stdout;
+        self.output = cli.common_options.output.unwrap_or_default();
     }
 }
 
@@ -147,8 +274,28 @@ impl Default for Options {
             examples: true,
             simplified: false,
             metadata: true,
+            overwrite: Overwrite::Ask,
+            create_dirs: false,
+            id_style: IdStyle::Legacy,
+            id_collision: IdCollision::Error,
+            id_fallback: IdFallback::Error,
+            min_id_length: 1,
+            max_id_length: None,
+            id_substitutions: Vec::new(),
+            semantic_roles: Vec::new(),
+            target_dirs: TargetDirs::default(),
+            editor: None,
             verbosity: Verbosity::Default,
             target_dir: ".".into(),
+            git_root: None,
+            topic: None,
+            edit: false,
+            git_add: false,
+            git_commit: None,
+            dry_run: false,
+            show_text: false,
+            stdout: false,
+            output: OutputFormat::Text,
         }
     }
 }
@@ -171,37 +318,115 @@ fn home_conf_file() -> Option<PathBuf> {
     Some(conf_file)
 }
 
-/// If the target location is in a Git repository, construct the path
-/// to a configuration file at the repository's root.
-/// Find all such configuration files if the Git repository is nested.
-fn git_conf_files(target_dir: &Path) -> Result<Vec<PathBuf>> {
-    let absolute_path = target_dir
-        .canonicalize()
-        .wrap_err("Failed to construct the absolute path to the target directory.")?;
+/// If the target location is in a Git repository, find the repository's root.
+/// Find all such roots if the Git repository is nested, from the innermost one.
+/// The `GIT_WORK_TREE` and `GIT_DIR` environment variables can set the root as well.
+fn git_roots(target_dir: &Path) -> Result<Vec<PathBuf>> {
+    // The target directory might not exist yet if newdoc is about to create it.
+    let absolute_path = absolute_path(target_dir)
+        .ok_or_else(|| eyre!("Failed to construct the absolute path to the target directory."))?;
+
+    Ok(find_git_roots(&absolute_path, env_work_tree()))
+}
+
+/// Find the Git roots among the ancestors of the absolute path.
+/// If the environment sets a work tree that contains the path, it counts as a root, too.
+fn find_git_roots(absolute_path: &Path, env_work_tree: Option<PathBuf>) -> Vec<PathBuf> {
     // Find all ancestor directories that appear to be the root of a Git repo.
-    let git_roots = absolute_path.ancestors().filter(|dir| {
-        // The simple heuristic is that the directory is the Git root if it contains
-        // the `.git/` sub-directory.
-        let git_dir = dir.join(".git");
-        log::debug!(
-            "Testing this directory as a Git repo root: {}",
-            git_dir.display()
-        );
-        git_dir.is_dir()
-    });
-
-    let config_files: Vec<_> = git_roots
-        .map(|root| {
-            log::debug!("Found a Git repo root: {}", root.display());
-            root.join(config_file_name(true))
-        })
+    let mut git_roots: Vec<PathBuf> = absolute_path
+        .ancestors()
+        .filter(|dir| is_git_root(dir))
+        .map(Path::to_path_buf)
         .collect();
 
-    Ok(config_files)
+    if let Some(work_tree) = env_work_tree {
+        if absolute_path.starts_with(&work_tree) && !git_roots.contains(&work_tree) {
+            log::debug!(
+                "Using the Git work tree from the environment: {}",
+                work_tree.display()
+            );
+            git_roots.push(work_tree);
+            // Keep the innermost root first.
+            git_roots.sort_by_key(|root| std::cmp::Reverse(root.components().count()));
+        }
+    }
+
+    for root in &git_roots {
+        log::debug!("Found a Git repo root: {}", root.display());
+    }
+
+    git_roots
+}
+
+/// Check if the directory is the root of a Git work tree. The `.git` entry is usually
+/// a directory, but in linked worktrees and submodules, it's a file with a `gitdir:`
+/// line that points to the actual Git directory.
+fn is_git_root(dir: &Path) -> bool {
+    let git_dir = dir.join(".git");
+    log::debug!(
+        "Testing this directory as a Git repo root: {}",
+        git_dir.display()
+    );
+
+    if git_dir.is_dir() {
+        return true;
+    }
+
+    if git_dir.is_file() {
+        let Ok(text) = fs::read_to_string(&git_dir) else {
+            log::warn!("Failed to read the `{}` file.", git_dir.display());
+            return false;
+        };
+        let Some(pointer) = text.lines().find_map(|line| line.strip_prefix("gitdir:")) else {
+            log::warn!("The `{}` file has no gitdir line.", git_dir.display());
+            return false;
+        };
+        // A relative pointer is relative to the directory that contains the `.git` file.
+        let target = dir.join(pointer.trim());
+        if target.is_dir() {
+            return true;
+        }
+        log::warn!(
+            "The `{}` file points to a missing Git directory: {}",
+            git_dir.display(),
+            target.display()
+        );
+    }
+
+    false
+}
+
+/// The root of the work tree that the Git environment variables set, if any.
+/// Without `GIT_WORK_TREE`, Git treats the current directory as the root when `GIT_DIR` is set.
+fn env_work_tree() -> Option<PathBuf> {
+    work_tree_from(|name| env::var_os(name))
+}
+
+/// The root of the work tree that the variables set, looked up by the function.
+fn work_tree_from(var: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    let work_tree = match var("GIT_WORK_TREE") {
+        Some(work_tree) => PathBuf::from(work_tree),
+        None if var("GIT_DIR").is_some() => env::current_dir().ok()?,
+        None => return None,
+    };
+
+    work_tree.canonicalize().ok()
+}
+
+/// Construct the paths to the configuration files at the roots of the Git repositories.
+fn git_conf_files(git_roots: &[PathBuf]) -> Vec<PathBuf> {
+    git_roots
+        .iter()
+        .map(|root| root.join(config_file_name(true)))
+        .collect()
 }
 
 /// Combine the configuration found on the command line, in configuration files,
 /// and in the defaults. Follows the standard hierarchy.
+///
+/// # Errors
+///
+/// Fails if a configuration file is invalid or if the target directory cannot be resolved.
 pub fn merge_configs(cli: &Cli) -> Result<Options> {
     // The default options are the base for further merging.
     let default_options = Options::default();
@@ -217,10 +442,18 @@ pub fn merge_configs(cli: &Cli) -> Result<Options> {
         // If the directory lookup fails because there's no home directory,
         // skip the processing of the home configuration file.
         log::warn!("Failed to locate a home directory. Skipping home configuration.");
-    };
+    }
+
+    // All Git repo roots around the target directory, from the innermost one:
+    let target_dir = cli
+        .common_options
+        .target_dir
+        .as_deref()
+        .unwrap_or(Path::new("."));
+    let git_roots = git_roots(target_dir)?;
 
     // All config files in Git repo roots:
-    let mut git_conf_files = git_conf_files(&cli.common_options.target_dir)?;
+    let mut git_conf_files = git_conf_files(&git_roots);
     // Reverse their order so that the inner repo configuration takes precedence over outer:
     git_conf_files.reverse();
     // Load each Git repo configuration file:
@@ -235,7 +468,247 @@ pub fn merge_configs(cli: &Cli) -> Result<Options> {
         .extract()
         .wrap_err("Failed to load configuration files.")?;
 
+    // The per-type target directories are relative to the innermost repository.
+    conf_options.git_root = git_roots.into_iter().next();
+
     conf_options.update_from_cli(cli);
+    lowercase_id_rules(&mut conf_options);
+    check_id_lengths(&conf_options)?;
+    check_target_dirs(&conf_options);
 
     Ok(conf_options)
 }
+
+/// Warn that the per-type target directories don't apply outside of a Git repository.
+fn check_target_dirs(options: &Options) {
+    if options.git_root.is_some() {
+        return;
+    }
+
+    let ignored: Vec<String> = ContentType::ALL
+        .into_iter()
+        .filter(|mod_type| options.target_dirs.get(*mod_type).is_some())
+        .map(|mod_type| mod_type.to_string())
+        .collect();
+
+    if !ignored.is_empty() {
+        log::warn!(
+            "Not in a Git repository. Ignoring the target directories for {}.",
+            ignored.join(", ")
+        );
+    }
+}
+
+/// Convert the configured ID rules to lower case, because they apply to the lower-case title.
+/// Otherwise, a rule such as `["C++", "cpp"]` would never match.
+fn lowercase_id_rules(options: &mut Options) {
+    for (old, _new) in &mut options.id_substitutions {
+        *old = old.to_lowercase();
+    }
+    for role in &mut options.semantic_roles {
+        *role = role.to_lowercase();
+    }
+}
+
+/// Check that the maximum ID length leaves room for an ID of the minimum length.
+fn check_id_lengths(options: &Options) -> Result<()> {
+    if let Some(max_id_length) = options.max_id_length {
+        if max_id_length == 0 || max_id_length < options.min_id_length {
+            bail!(
+                "The maximum ID length, {max_id_length}, is shorter than the minimum ID length, {}. \
+                Set the `max_id_length` option to at least {}.",
+                options.min_id_length,
+                options.min_id_length.max(1)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::Input;
+
+    #[test]
+    fn per_type_target_dirs() {
+        let options = Options {
+            target_dir: PathBuf::from("fallback"),
+            target_dirs: TargetDirs {
+                assembly: Some("assemblies".to_string()),
+                procedure: Some("modules/{topic}".to_string()),
+                ..Default::default()
+            },
+            git_root: Some(PathBuf::from("/repo")),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options
+                .target_dir_for(ContentType::Assembly)
+                .expect("Failed to resolve a directory"),
+            PathBuf::from("/repo/assemblies")
+        );
+        assert_eq!(
+            options
+                .target_dir_for(ContentType::Concept)
+                .expect("Failed to resolve a directory"),
+            PathBuf::from("fallback")
+        );
+        assert!(options.target_dir_for(ContentType::Procedure).is_err());
+
+        let options = Options {
+            topic: Some("installing".to_string()),
+            ..options
+        };
+        assert_eq!(
+            options
+                .target_dir_for(ContentType::Procedure)
+                .expect("Failed to resolve a directory"),
+            PathBuf::from("/repo/modules/installing")
+        );
+    }
+
+    /// Create an empty temporary directory for a repository layout.
+    /// Returns the directory, which is removed when dropped, and its canonical path.
+    fn layout() -> (tempfile::TempDir, PathBuf) {
+        let temp = tempfile::tempdir().expect("Failed to create a temporary directory");
+        let root = temp
+            .path()
+            .canonicalize()
+            .expect("Failed to canonicalize a path");
+        (temp, root)
+    }
+
+    #[test]
+    fn worktree_root() {
+        let (_temp, root) = layout();
+        let main = root.join("main");
+        let worktree = root.join("worktree");
+        fs::create_dir_all(main.join(".git/worktrees/worktree")).expect("Failed to create a dir");
+        fs::create_dir_all(worktree.join("modules")).expect("Failed to create a dir");
+        fs::write(
+            worktree.join(".git"),
+            format!(
+                "gitdir: {}\n",
+                main.join(".git/worktrees/worktree").display()
+            ),
+        )
+        .expect("Failed to write a file");
+
+        assert_eq!(
+            find_git_roots(&worktree.join("modules"), None),
+            vec![worktree.clone()]
+        );
+    }
+
+    #[test]
+    fn submodule_root() {
+        let (_temp, root) = layout();
+        let superproject = root.join("docs");
+        let submodule = superproject.join("shared");
+        fs::create_dir_all(superproject.join(".git/modules/shared"))
+            .expect("Failed to create a dir");
+        fs::create_dir_all(&submodule).expect("Failed to create a dir");
+        // Submodules use a relative pointer.
+        fs::write(submodule.join(".git"), "gitdir: ../.git/modules/shared\n")
+            .expect("Failed to write a file");
+
+        // The submodule is the inner repository, so it comes first.
+        assert_eq!(
+            find_git_roots(&submodule, None),
+            vec![submodule.clone(), superproject.clone()]
+        );
+
+        // A `.git` file that points nowhere isn't a repository.
+        fs::write(submodule.join(".git"), "gitdir: ../.git/modules/missing\n")
+            .expect("Failed to write a file");
+        assert_eq!(find_git_roots(&submodule, None), vec![superproject.clone()]);
+    }
+
+    #[test]
+    fn environment_work_tree() {
+        let (_temp, root) = layout();
+        let work_tree = root.join("checkout");
+        fs::create_dir_all(work_tree.join("modules")).expect("Failed to create a dir");
+
+        assert_eq!(
+            find_git_roots(&work_tree.join("modules"), None),
+            Vec::<PathBuf>::new()
+        );
+        assert_eq!(
+            find_git_roots(&work_tree.join("modules"), Some(work_tree.clone())),
+            vec![work_tree.clone()]
+        );
+        // A work tree that doesn't contain the target directory doesn't apply.
+        assert_eq!(
+            find_git_roots(&root, Some(work_tree.clone())),
+            Vec::<PathBuf>::new()
+        );
+    }
+
+    #[test]
+    fn work_tree_variables() {
+        let (_temp, root) = layout();
+        let unset = |_: &str| None;
+        assert_eq!(work_tree_from(unset), None);
+
+        let work_tree = |name: &str| (name == "GIT_WORK_TREE").then(|| root.clone().into());
+        assert_eq!(work_tree_from(work_tree), Some(root.clone()));
+
+        // A work tree that doesn't exist doesn't apply.
+        let missing = |name: &str| (name == "GIT_WORK_TREE").then(|| root.join("missing").into());
+        assert_eq!(work_tree_from(missing), None);
+
+        // With only `GIT_DIR`, the current directory is the root.
+        let git_dir = |name: &str| (name == "GIT_DIR").then(|| root.join(".git").into());
+        let current_dir = env::current_dir()
+            .and_then(|dir| dir.canonicalize())
+            .expect("Failed to find the current directory");
+        assert_eq!(work_tree_from(git_dir), Some(current_dir));
+
+        // `GIT_WORK_TREE` takes precedence over `GIT_DIR`.
+        let both = |name: &str| match name {
+            "GIT_WORK_TREE" => Some(root.clone().into()),
+            "GIT_DIR" => Some(root.join("elsewhere/.git").into()),
+            _ => None,
+        };
+        assert_eq!(work_tree_from(both), Some(root.clone()));
+    }
+
+    #[test]
+    fn id_length_limits() {
+        let options = |max_id_length, min_id_length| Options {
+            min_id_length,
+            max_id_length,
+            ..Default::default()
+        };
+
+        assert!(check_id_lengths(&options(None, 2)).is_ok());
+        assert!(check_id_lengths(&options(Some(2), 2)).is_ok());
+        assert!(check_id_lengths(&options(Some(1), 2)).is_err());
+        assert!(check_id_lengths(&options(Some(0), 0)).is_err());
+    }
+
+    #[test]
+    fn upper_case_id_rules() {
+        let mut options = Options {
+            id_substitutions: vec![("C++".to_string(), "cpp".to_string())],
+            semantic_roles: vec!["GuiLabel".to_string()],
+            ..Default::default()
+        };
+        lowercase_id_rules(&mut options);
+
+        assert_eq!(
+            options.id_substitutions,
+            [("c++".to_string(), "cpp".to_string())]
+        );
+        let input = Input::new(
+            ContentType::Concept,
+            "Using C++ with [GuiLabel]`Build`",
+            &options,
+        );
+        assert_eq!(input.id(), "using-cpp-with-build");
+    }
+}
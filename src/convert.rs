@@ -0,0 +1,714 @@
+/*
+newdoc: Generate pre-populated documentation modules formatted with AsciiDoc.
+Copyright (C) 2026  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! # `convert.rs`
+//!
+//! This module turns a document that has been split into sections
+//! into modules and an assembly that includes them.
+//! The parsers for the individual source formats produce the `Document` struct.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use dialoguer::{theme::ColorfulTheme, Select};
+use regex::{Captures, Regex};
+
+use crate::collisions::Existing;
+use crate::docbook;
+use crate::html;
+use crate::markdown;
+use crate::module::{absolute_path, is_valid_id, ContentType, Input, Module};
+use crate::write::{self, Replaced};
+use crate::{Options, REGEX_ERROR};
+
+/// An attribute reference in AsciiDoc, such as `{product}`
+const ATTRIBUTE_REFERENCE: &str = r"\{[A-Za-z0-9_][A-Za-z0-9_-]*\}";
+
+/// The URL schemes of external links. Other link targets are relative paths.
+const URL_SCHEMES: [&str; 4] = ["http://", "https://", "ftp://", "mailto:"];
+
+/// Convert a document in another markup format into modules and an assembly
+/// in the target directory. The file extension determines the source format.
+pub fn import(file: &Path, options: &Options, interactive: bool) -> Result<()> {
+    let text = fs::read_to_string(file)
+        .wrap_err_with(|| eyre!("Failed to read the `{}` file.", file.display()))?;
+
+    let extension = file
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+
+    let document = match extension.as_deref() {
+        Some("md" | "markdown") => markdown::parse(&text),
+        Some("xml" | "docbook" | "dbk") => docbook::parse(&text),
+        Some("html" | "htm") => html::parse(&text),
+        _ => bail!(
+            "Cannot import the `{}` file. The supported file extensions are \
+            `.md` and `.markdown` for Markdown, `.xml`, `.docbook`, and `.dbk` for DocBook, \
+            and `.html` and `.htm` for HTML.",
+            file.display()
+        ),
+    }
+    .wrap_err_with(|| eyre!("Failed to convert the `{}` file.", file.display()))?;
+
+    log::debug!(
+        "Importing `{}` as {} modules.",
+        file.display(),
+        document.sections.len()
+    );
+
+    document.write(options, None, interactive)
+}
+
+/// A piece of a converted document, before it's split into sections.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Block {
+    /// A heading at this level, where the highest level is 1
+    Heading {
+        level: usize,
+        title: String,
+        id: Option<String>,
+    },
+    /// AsciiDoc content between the headings
+    Content(String),
+}
+
+/// A document that has been split into sections, already converted to AsciiDoc.
+#[derive(Debug)]
+pub struct Document {
+    pub title: String,
+    /// The ID of the whole document, if the source sets one
+    pub id: Option<String>,
+    /// The content before the first section, which stays in the assembly
+    pub preamble: String,
+    pub sections: Vec<Section>,
+}
+
+/// A single section of the document, which becomes a module.
+#[derive(Debug)]
+pub struct Section {
+    pub title: String,
+    /// The ID of the section, if the source sets one
+    pub id: Option<String>,
+    /// The content type, if the source format declares it.
+    /// Otherwise, newdoc infers it from the body.
+    pub mod_type: Option<ContentType>,
+    /// The content under the title, with the subsection headings starting at level 1
+    pub body: String,
+}
+
+impl Document {
+    /// Split the converted blocks into sections at the highest heading level below the title.
+    /// The first heading becomes the title, unless the source sets the title separately.
+    pub fn from_blocks(blocks: Vec<Block>, title: Option<String>) -> Result<Self> {
+        let mut blocks = blocks.into_iter().peekable();
+        let mut preamble = String::new();
+
+        // Use the first heading as the title, if the source doesn't set it elsewhere.
+        let (title, id) = if let Some(title) = title {
+            (title, None)
+        } else {
+            loop {
+                match blocks.next() {
+                    Some(Block::Heading { title, id, .. }) => break (title, id),
+                    Some(Block::Content(content)) => preamble.push_str(&content),
+                    None => bail!("The document has no title. Add a heading at the start."),
+                }
+            }
+        };
+
+        let blocks: Vec<Block> = blocks.collect();
+
+        let Some(split_level) = blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Heading { level, .. } => Some(*level),
+                Block::Content(_) => None,
+            })
+            .min()
+        else {
+            bail!("The document has no sections to split into modules.");
+        };
+
+        let mut sections: Vec<Section> = Vec::new();
+
+        for block in blocks {
+            let in_preamble = sections.is_empty();
+            // The content before the first section stays in the assembly.
+            let body = match sections.last_mut() {
+                Some(section) => &mut section.body,
+                None => &mut preamble,
+            };
+
+            match block {
+                Block::Heading { level, title, id } if level == split_level => {
+                    sections.push(Section {
+                        title,
+                        id,
+                        mod_type: None,
+                        body: String::new(),
+                    });
+                }
+                // Shift the subsection headings so that the section title becomes level 0.
+                // Headings in the preamble can't form sections in the assembly.
+                Block::Heading { level, title, .. } => {
+                    let depth = level - split_level + 1;
+                    if in_preamble {
+                        body.push_str("[discrete]\n");
+                    }
+                    body.push_str(&"=".repeat(depth));
+                    body.push(' ');
+                    body.push_str(&title);
+                    body.push_str("\n\n");
+                }
+                Block::Content(content) => body.push_str(&content),
+            }
+        }
+
+        Ok(Self {
+            title,
+            id,
+            preamble: preamble.trim().to_string(),
+            sections,
+        })
+    }
+
+    /// Prepare the new module for each section, with the content type that the user
+    /// chooses in the interactive mode, or that newdoc infers.
+    fn inputs(&self, options: &Options, interactive: bool) -> Result<Vec<Input>> {
+        let mut inputs: Vec<Input> = Vec::new();
+
+        for section in &self.sections {
+            let inferred = section
+                .mod_type
+                .unwrap_or_else(|| infer_content_type(&section.body));
+            let mod_type = if interactive {
+                choose_content_type(&section.title, inferred)?
+            } else {
+                inferred
+            };
+
+            let mut input = Input::new(mod_type, &section.title, options);
+            if let Some(id) = checked_id(section.id.as_deref(), &section.title) {
+                input = input.with_id(id);
+            }
+
+            log::debug!("Section `{}` becomes a {mod_type}.", section.title);
+
+            inputs.push(input);
+        }
+
+        Ok(inputs)
+    }
+
+    /// Convert the sections to modules in the target directory, and the whole document
+    /// to an assembly that includes them.
+    ///
+    /// If `assembly_path` is set, the assembly replaces that file and includes the modules
+    /// by their paths relative to it. Otherwise, newdoc writes the assembly
+    /// to the target directory as well.
+    ///
+    /// With `interactive`, newdoc asks for the content type of each section.
+    pub fn write(
+        &self,
+        options: &Options,
+        assembly_path: Option<&Path>,
+        interactive: bool,
+    ) -> Result<()> {
+        if self.sections.is_empty() {
+            bail!(
+                "The document `{}` contains no sections to convert to modules.",
+                self.title
+            );
+        }
+
+        let inputs = self.inputs(options, interactive)?;
+
+        // Check the new IDs and file names against the existing content in the repository.
+        // The document that becomes the assembly doesn't count, because the modules replace it.
+        let mut existing = Existing::scan(options);
+        if let Some(path) = assembly_path {
+            existing.forget(path);
+        }
+        let inputs = inputs
+            .into_iter()
+            .map(|input| existing.resolve(input, options))
+            .collect::<Result<Vec<Input>>>()?;
+
+        // The cross-references can point to a section by its ID in the source document,
+        // or by the ID that newdoc derives from its title, such as a Markdown heading link.
+        // Snippets don't declare an ID, so the cross-references to them stay unchanged.
+        let mut targets: HashMap<String, String> = HashMap::new();
+        for (section, input) in self.sections.iter().zip(&inputs) {
+            if input.mod_type == ContentType::Snippet {
+                continue;
+            }
+            let anchor = input.anchor();
+            if let Some(id) = &section.id {
+                targets.insert(id.clone(), anchor.clone());
+            }
+            targets.insert(input.id(), anchor.clone());
+            targets.insert(anchor.clone(), anchor);
+        }
+
+        let mut modules: Vec<Module> = self
+            .sections
+            .iter()
+            .zip(inputs)
+            .map(|(section, input)| {
+                input
+                    .body(&link_modules(&section.body, &targets, options.simplified))
+                    .into()
+            })
+            .collect();
+
+        // Two sections with the same title would overwrite each other's file.
+        let mut file_names = HashSet::new();
+        for module in &modules {
+            if !file_names.insert(&module.file_name) {
+                bail!(
+                    "Several sections result in the same file name, `{}`. \
+                    Give the sections unique titles or IDs.",
+                    module.file_name
+                );
+            }
+        }
+
+        // The replaced document includes the modules by their paths relative to it.
+        // Store the actual statement in the module so that the report shows it.
+        if let Some(path) = assembly_path {
+            for module in &mut modules {
+                let include_dir = relative_include_dir(path, module.target_dir())?;
+                let include_path = include_dir.join(&module.file_name);
+                module.include_statement =
+                    format!("include::{}[leveloffset=+1]", include_path.display());
+            }
+        }
+
+        let include_statements = modules
+            .iter()
+            .map(|module| module.include_statement.clone())
+            .collect();
+
+        let mut input = Input::new(ContentType::Assembly, &self.title, options)
+            .body(&link_modules(&self.preamble, &targets, options.simplified));
+        if let Some(id) = checked_id(self.id.as_deref(), &self.title) {
+            input = input.with_id(id);
+        }
+        // Only a new assembly can collide with the existing content.
+        if assembly_path.is_none() {
+            input = existing.resolve(input, options)?;
+        }
+        let assembly: Module = input.include(include_statements).into();
+
+        // The assembly either replaces the source document or joins the new modules.
+        let mut new_files: Vec<&Module> = modules.iter().collect();
+        if assembly_path.is_none() {
+            new_files.push(&assembly);
+        }
+
+        // A converted source document is replaced as part of the same batch as the modules.
+        // The dry run only previews it.
+        let replaced: Vec<Replaced> = match assembly_path {
+            Some(path) => {
+                if options.dry_run {
+                    log::info!("‣ File to convert to an assembly: {}", path.display());
+                    for include in assembly.text.lines().filter(|l| l.starts_with("include::")) {
+                        log::info!("  {include}");
+                    }
+                    if options.show_text {
+                        println!("{}", assembly.text);
+                    }
+                }
+                vec![Replaced {
+                    module: &assembly,
+                    path: path.to_path_buf(),
+                    text: &assembly.text,
+                }]
+            }
+            None => Vec::new(),
+        };
+
+        let outcomes = write::write_modules(&new_files, &replaced, options)?;
+
+        if let Some(path) = assembly_path {
+            if !options.dry_run && !options.stdout {
+                log::info!("‣ File converted to an assembly: {}", path.display());
+            }
+        }
+
+        write::check_skipped(&outcomes)
+    }
+}
+
+/// Format a link from the source document as an AsciiDoc link, cross-reference, or bare URL.
+/// A target that starts with `#` points to an ID in the same document.
+pub fn link(target: &str, text: &str) -> String {
+    if let Some(id) = target.strip_prefix('#') {
+        return xref(id, text);
+    }
+
+    let text = text.replace(']', "\\]");
+    let has_scheme = URL_SCHEMES.iter().any(|scheme| target.starts_with(scheme));
+
+    if has_scheme && (text.is_empty() || text == target) {
+        target.to_string()
+    } else if has_scheme {
+        format!("{target}[{text}]")
+    } else {
+        format!("link:{target}[{text}]")
+    }
+}
+
+/// Format a cross-reference to an ID in the same document.
+pub fn xref(id: &str, text: &str) -> String {
+    if text.is_empty() {
+        format!("<<{id}>>")
+    } else {
+        format!("<<{id},{text}>>")
+    }
+}
+
+/// Escape the attribute references, such as `{product}`, in plain text from the source document,
+/// so that AsciiDoc shows them literally.
+pub fn escape_attributes(text: &str) -> String {
+    let reference = Regex::new(ATTRIBUTE_REFERENCE).expect(REGEX_ERROR);
+    reference.replace_all(text, r"\$0").into_owned()
+}
+
+/// Escape the plain text from the source document, so that AsciiDoc shows it literally
+/// rather than as attribute references or inline formatting.
+pub fn escape(text: &str) -> String {
+    let reference = Regex::new(ATTRIBUTE_REFERENCE).expect(REGEX_ERROR);
+    let mut escaped = String::new();
+    let mut last = 0;
+
+    for found in reference.find_iter(text) {
+        escaped.push_str(&escape_marks(&text[last..found.start()]));
+        escaped.push('\\');
+        escaped.push_str(found.as_str());
+        last = found.end();
+    }
+    escaped.push_str(&escape_marks(&text[last..]));
+
+    escaped
+}
+
+/// Replace the inline formatting marks with the built-in character attributes,
+/// or with passthroughs for the marks that have no attribute.
+fn escape_marks(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '*' => "{asterisk}".to_string(),
+            '+' => "{plus}".to_string(),
+            '`' => "{backtick}".to_string(),
+            '^' => "{caret}".to_string(),
+            '~' => "{tilde}".to_string(),
+            '_' | '#' => format!("++{c}++"),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+/// Collapse the whitespace in the text, including line breaks, into single spaces.
+pub fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Use the ID from the source document only if it's safe as an anchor and a file name.
+/// Otherwise, warn and let newdoc derive the ID from the title.
+fn checked_id<'a>(id: Option<&'a str>, title: &str) -> Option<&'a str> {
+    let id = id?;
+    if is_valid_id(id) {
+        Some(id)
+    } else {
+        log::warn!(
+            "The `{id}` ID of `{title}` can only contain ASCII letters, digits, \
+            dashes, underscores, and periods. Using an ID based on the title instead."
+        );
+        None
+    }
+}
+
+/// Point the cross-references to the modules at their anchors with the context,
+/// such as `<<installing_{context}>>`, because the modules declare their IDs that way.
+/// In the simplified mode, the modules declare their IDs without the context.
+/// Cross-references to other IDs stay unchanged.
+fn link_modules(text: &str, targets: &HashMap<String, String>, simplified: bool) -> String {
+    let xref = Regex::new(r"<<([^,>\s]+)(,[^>]*)?>>").expect(REGEX_ERROR);
+
+    xref.replace_all(text, |captures: &Captures| {
+        match targets.get(&captures[1]) {
+            Some(anchor) => {
+                let text = captures.get(2).map_or("", |text| text.as_str());
+                if simplified {
+                    format!("<<{anchor}{text}>>")
+                } else {
+                    format!("<<{anchor}_{{context}}{text}>>")
+                }
+            }
+            None => captures[0].to_string(),
+        }
+    })
+    .into_owned()
+}
+
+/// Guess the content type of a section from its AsciiDoc body.
+/// Numbered steps suggest a procedure, and tables or definition lists suggest a reference.
+/// All other sections are concepts.
+pub fn infer_content_type(body: &str) -> ContentType {
+    let is_procedure = body.lines().any(|line| {
+        line.starts_with(".Procedure")
+            || line.starts_with(". ")
+            || line.split_once(". ").is_some_and(|(number, _)| {
+                !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+            })
+    });
+    if is_procedure {
+        return ContentType::Procedure;
+    }
+
+    let is_reference = body
+        .lines()
+        .any(|line| line.starts_with("|===") || line.contains(":: ") || line.ends_with("::"));
+    if is_reference {
+        return ContentType::Reference;
+    }
+
+    ContentType::Concept
+}
+
+/// Ask the user for the content type of the section, offering the inferred type as the default.
+fn choose_content_type(title: &str, inferred: ContentType) -> Result<ContentType> {
+    let choices = [
+        ContentType::Concept,
+        ContentType::Procedure,
+        ContentType::Reference,
+        ContentType::Assembly,
+        ContentType::Snippet,
+    ];
+    let default = choices
+        .iter()
+        .position(|mod_type| *mod_type == inferred)
+        .unwrap_or_default();
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("Content type of the `{title}` section"))
+        .items(&choices)
+        .default(default)
+        .interact()?;
+
+    Ok(choices[selection])
+}
+
+/// Find the path from the directory of the assembly to the target directory of the modules.
+fn relative_include_dir(assembly_path: &Path, target_dir: &Path) -> Result<PathBuf> {
+    let assembly_dir = match assembly_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let from = assembly_dir.canonicalize().wrap_err_with(|| {
+        eyre!(
+            "Failed to access the `{}` directory.",
+            assembly_dir.display()
+        )
+    })?;
+    // The target directory might not exist yet if newdoc is about to create it.
+    let to = absolute_path(target_dir)
+        .ok_or_else(|| eyre!("Failed to access the `{}` directory.", target_dir.display()))?;
+
+    Ok(relative_path(&from, &to))
+}
+
+/// Construct the relative path between two absolute directories.
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+
+    let common = from
+        .iter()
+        .zip(&to)
+        .take_while(|(from, to)| from == to)
+        .count();
+
+    let mut path: PathBuf = from[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .collect();
+    path.extend(&to[common..]);
+
+    if path.as_os_str().is_empty() {
+        PathBuf::new()
+    } else {
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_type_inference() {
+        assert_eq!(
+            infer_content_type(".Procedure\n\n. Open the file.\n. Save it.\n"),
+            ContentType::Procedure
+        );
+        assert_eq!(
+            infer_content_type("1. First step\n2. Second step\n"),
+            ContentType::Procedure
+        );
+        assert_eq!(
+            infer_content_type("|===\n| Option | Value\n|===\n"),
+            ContentType::Reference
+        );
+        assert_eq!(
+            infer_content_type("Term:: Definition\n"),
+            ContentType::Reference
+        );
+        assert_eq!(
+            infer_content_type("Some explanation. And more.\n"),
+            ContentType::Concept
+        );
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(link("#installing", ""), "<<installing>>");
+        assert_eq!(link("#installing", "Install"), "<<installing,Install>>");
+        assert_eq!(link("https://example.com", ""), "https://example.com");
+        assert_eq!(
+            link("https://example.com", "https://example.com"),
+            "https://example.com"
+        );
+        assert_eq!(
+            link("https://example.com", "[1] Example"),
+            "https://example.com[[1\\] Example]"
+        );
+        assert_eq!(link("guide.html", "Guide"), "link:guide.html[Guide]");
+        assert_eq!(collapse("  Two\n  words "), "Two words");
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("Install {product}."), "Install \\{product}.");
+        assert_eq!(escape("{ not an attribute }"), "{ not an attribute }");
+        assert_eq!(escape("2 * 3 + 1"), "2 {asterisk} 3 {plus} 1");
+        assert_eq!(escape("C# and my_var"), "C++#++ and my++_++var");
+        assert_eq!(escape("{my_attr}_"), "\\{my_attr}++_++");
+        assert_eq!(
+            escape_attributes("Using {product} *now*"),
+            "Using \\{product} *now*"
+        );
+    }
+
+    #[test]
+    fn module_cross_references() {
+        let targets = HashMap::from([
+            ("installing".to_string(), "installing".to_string()),
+            ("about".to_string(), "con_about".to_string()),
+        ]);
+
+        assert_eq!(
+            link_modules(
+                "See <<installing>> and <<about,the overview>>.",
+                &targets,
+                false
+            ),
+            "See <<installing_{context}>> and <<con_about_{context},the overview>>."
+        );
+        assert_eq!(
+            link_modules("See <<elsewhere>>.", &targets, false),
+            "See <<elsewhere>>."
+        );
+    }
+
+    #[test]
+    fn simplified_module_cross_references() {
+        let targets = HashMap::from([
+            ("installing".to_string(), "installing".to_string()),
+            ("about".to_string(), "con_about".to_string()),
+        ]);
+
+        assert_eq!(
+            link_modules(
+                "See <<installing>> and <<about,the overview>>.",
+                &targets,
+                true
+            ),
+            "See <<installing>> and <<con_about,the overview>>."
+        );
+        assert_eq!(
+            link_modules("See <<elsewhere>>.", &targets, true),
+            "See <<elsewhere>>."
+        );
+    }
+
+    #[test]
+    fn hostile_section_ids() {
+        let document = Document {
+            title: "Guide".to_string(),
+            id: Some("../guide".to_string()),
+            preamble: String::new(),
+            sections: vec![
+                Section {
+                    title: "Installing".to_string(),
+                    id: Some("../../x".to_string()),
+                    mod_type: Some(ContentType::Procedure),
+                    body: String::new(),
+                },
+                Section {
+                    title: "About".to_string(),
+                    id: Some("about-it".to_string()),
+                    mod_type: Some(ContentType::Concept),
+                    body: String::new(),
+                },
+            ],
+        };
+        let options = Options::default();
+        let inputs = document
+            .inputs(&options, false)
+            .expect("Failed to prepare the modules");
+
+        assert_eq!(inputs[0].anchor(), "installing");
+        assert_eq!(inputs[0].file_name(), "proc_installing.adoc");
+        assert_eq!(inputs[1].anchor(), "about-it");
+        assert_eq!(checked_id(Some("a/b"), "Guide"), None);
+        assert_eq!(checked_id(document.id.as_deref(), "Guide"), None);
+        assert_eq!(checked_id(Some("guide_1.0"), "Guide"), Some("guide_1.0"));
+    }
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(
+            relative_path(Path::new("/docs/assemblies"), Path::new("/docs/modules")),
+            PathBuf::from("../modules")
+        );
+        assert_eq!(
+            relative_path(Path::new("/docs"), Path::new("/docs/modules")),
+            PathBuf::from("modules")
+        );
+        assert_eq!(
+            relative_path(Path::new("/docs"), Path::new("/docs")),
+            PathBuf::new()
+        );
+    }
+}
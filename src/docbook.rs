@@ -0,0 +1,590 @@
+/*
+newdoc: Generate pre-populated documentation modules formatted with AsciiDoc.
+Copyright (C) 2026  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! # `docbook.rs`
+//!
+//! This module converts a DocBook XML document to AsciiDoc sections.
+//! Each top-level section or chapter becomes a module, with the content type
+//! based on its content: sections with a `<procedure>` become procedures,
+//! sections with tables or variable lists become references, and the rest become concepts.
+
+use std::collections::HashSet;
+
+use color_eyre::eyre::{bail, Result, WrapErr};
+use roxmltree::{Node, ParsingOptions, NS_XML_URI};
+
+use crate::convert::{collapse, escape, escape_attributes, link, xref, Document, Section};
+use crate::module::ContentType;
+
+/// The elements that form sections, which can become modules.
+const SECTIONS: [&str; 11] = [
+    "chapter",
+    "section",
+    "sect1",
+    "sect2",
+    "sect3",
+    "sect4",
+    "sect5",
+    "simplesect",
+    "preface",
+    "appendix",
+    "refsection",
+];
+
+/// The elements that newdoc reads as part of their parent element, rather than as content.
+const METADATA: [&str; 5] = ["title", "subtitle", "titleabbrev", "info", "indexterm"];
+
+/// Parse the DocBook XML text and convert it to a document split into sections.
+pub fn parse(text: &str) -> Result<Document> {
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let xml = roxmltree::Document::parse_with_options(text, options)
+        .wrap_err("The file is not valid XML.")?;
+    let root = xml.root_element();
+
+    let Some(title) = title_of(root) else {
+        bail!(
+            "The root `<{}>` element has no title.",
+            root.tag_name().name()
+        );
+    };
+
+    let section_nodes: Vec<Node> = root.children().filter(|node| is_section(*node)).collect();
+
+    let mut converter = Converter {
+        reported: HashSet::new(),
+    };
+
+    // The content outside of the sections stays in the assembly.
+    let preamble_nodes: Vec<Node> = root.children().filter(|node| !is_section(*node)).collect();
+    let mut preamble = converter.blocks(&preamble_nodes, 1);
+    if let Some(abstract_node) = child(root, "info").and_then(|info| child(info, "abstract")) {
+        let summary = converter.blocks(&abstract_node.children().collect::<Vec<_>>(), 1);
+        preamble = [summary, preamble].join("\n\n");
+    }
+
+    let sections = section_nodes
+        .iter()
+        .map(|node| {
+            let children: Vec<Node> = node.children().collect();
+            Section {
+                title: title_of(*node).unwrap_or_default(),
+                id: id_of(*node),
+                mod_type: Some(content_type(*node)),
+                body: converter.blocks(&children, 1),
+            }
+        })
+        .collect();
+
+    Ok(Document {
+        title,
+        id: id_of(root),
+        preamble: preamble.trim().to_string(),
+        sections,
+    })
+}
+
+/// Decide the content type of a DocBook section from the elements that it contains.
+fn content_type(section: Node) -> ContentType {
+    let contains = |names: &[&str]| {
+        section
+            .descendants()
+            .any(|node| names.contains(&node.tag_name().name()))
+    };
+
+    if contains(&["procedure"]) {
+        ContentType::Procedure
+    } else if contains(&["table", "informaltable", "variablelist", "refentry"]) {
+        ContentType::Reference
+    } else {
+        ContentType::Concept
+    }
+}
+
+/// The state of the conversion from DocBook elements to AsciiDoc.
+struct Converter {
+    /// The unsupported elements that newdoc already reported, to report each only once
+    reported: HashSet<String>,
+}
+
+impl Converter {
+    /// Convert a sequence of block elements, separated by blank lines.
+    /// The depth is the level of the headings for the nested sections.
+    fn blocks(&mut self, nodes: &[Node], depth: usize) -> String {
+        let mut blocks = Vec::new();
+
+        for node in nodes {
+            if node.is_text() {
+                let text = escape(&collapse(node.text().unwrap_or_default()));
+                if !text.is_empty() {
+                    blocks.push(text);
+                }
+            } else if node.is_element() && !METADATA.contains(&node.tag_name().name()) {
+                let block = self.block(*node, depth);
+                if !block.trim().is_empty() {
+                    blocks.push(block.trim_end().to_string());
+                }
+            }
+        }
+
+        blocks.join("\n\n")
+    }
+
+    /// Convert a single block element.
+    fn block(&mut self, node: Node, depth: usize) -> String {
+        let name = node.tag_name().name();
+        let children: Vec<Node> = node.children().collect();
+
+        match name {
+            "para" | "simpara" => self.inline(node),
+            "formalpara" => format!(
+                "{}{}",
+                self.block_title(node),
+                child(node, "para")
+                    .map(|para| self.inline(para))
+                    .unwrap_or_default()
+            ),
+            _ if is_section(node) => {
+                let id = id_of(node)
+                    .map(|id| format!("[id=\"{id}\"]\n"))
+                    .unwrap_or_default();
+                format!(
+                    "{id}{} {}\n\n{}",
+                    "=".repeat(depth + 1),
+                    title_of(node).unwrap_or_default(),
+                    self.blocks(&children, depth + 1)
+                )
+            }
+            "itemizedlist" => self.list(node, "*", "listitem"),
+            "orderedlist" => self.list(node, ".", "listitem"),
+            "procedure" => {
+                let title = match title_of(node) {
+                    Some(title) => format!(".{title}\n"),
+                    None => ".Procedure\n".to_string(),
+                };
+                format!("{title}\n{}", self.list(node, ".", "step"))
+            }
+            "substeps" => self.list(node, ".", "step"),
+            "variablelist" => {
+                let entries = node
+                    .children()
+                    .filter(|entry| entry.has_tag_name("varlistentry"))
+                    .map(|entry| {
+                        let term = child(entry, "term")
+                            .map(|term| self.inline(term))
+                            .unwrap_or_default();
+                        let definition = child(entry, "listitem")
+                            .map(|item| self.item_content(item))
+                            .unwrap_or_default();
+                        format!("{term}::\n{definition}")
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                format!("{}{entries}", self.block_title(node))
+            }
+            "programlisting" | "screen" | "synopsis" => {
+                let source = match node.attribute("language") {
+                    Some(language) => format!("[source,{language}]\n"),
+                    None => String::new(),
+                };
+                format!(
+                    "{}{source}----\n{}\n----",
+                    self.block_title(node),
+                    verbatim(node).trim_matches('\n')
+                )
+            }
+            "literallayout" => format!("....\n{}\n....", verbatim(node).trim_matches('\n')),
+            "note" | "tip" | "important" | "warning" | "caution" => format!(
+                "[{}]\n{}====\n{}\n====",
+                name.to_uppercase(),
+                self.block_title(node),
+                self.blocks(&children, depth)
+            ),
+            "example" | "sidebar" => {
+                let delimiter = if name == "example" { "====" } else { "****" };
+                format!(
+                    "{}{delimiter}\n{}\n{delimiter}",
+                    self.block_title(node),
+                    self.blocks(&children, depth)
+                )
+            }
+            "blockquote" => format!("____\n{}\n____", self.blocks(&children, depth)),
+            "table" | "informaltable" => self.table(node),
+            "figure" | "informalfigure" | "mediaobject" => {
+                let image = node
+                    .descendants()
+                    .find(|image| image.has_tag_name("imagedata"))
+                    .and_then(|image| image.attribute("fileref"));
+                if let Some(file) = image {
+                    format!("{}image::{file}[]", self.block_title(node))
+                } else {
+                    self.report(name);
+                    String::new()
+                }
+            }
+            // Wrapper elements without their own meaning in AsciiDoc
+            "partintro" | "abstract" | "refsect1" | "refentry" => self.blocks(&children, depth),
+            _ => {
+                self.report(name);
+                // Keep the content of the unknown element, as blocks if it has any,
+                // or as a paragraph.
+                if children.iter().any(|node| is_block(*node)) {
+                    self.blocks(&children, depth)
+                } else {
+                    self.inline(node)
+                }
+            }
+        }
+    }
+
+    /// Convert a list, where each item of the `item_name` element starts with the marker.
+    /// Nested lists repeat the marker character.
+    fn list(&mut self, node: Node, marker: &str, item_name: &str) -> String {
+        let nesting = node
+            .ancestors()
+            .filter(|ancestor| {
+                matches!(
+                    ancestor.tag_name().name(),
+                    "itemizedlist" | "orderedlist" | "procedure" | "substeps"
+                )
+            })
+            .count();
+        let marker = marker.repeat(nesting);
+
+        node.children()
+            .filter(|item| item.has_tag_name(item_name))
+            .map(|item| format!("{marker} {}", self.item_content(item)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Convert the content of a list item. The blocks after the first one
+    /// attach to the item with the list continuation.
+    fn item_content(&mut self, item: Node) -> String {
+        let mut blocks: Vec<String> = Vec::new();
+
+        for node in item.children() {
+            if node.is_element() && !METADATA.contains(&node.tag_name().name()) {
+                let block = self.block(node, 1);
+                let is_list = matches!(
+                    node.tag_name().name(),
+                    "itemizedlist" | "orderedlist" | "substeps"
+                );
+                if block.trim().is_empty() {
+                    continue;
+                }
+                if blocks.is_empty() || is_list {
+                    blocks.push(format!("\n{}", block.trim_end()));
+                } else {
+                    blocks.push(format!("\n+\n{}", block.trim_end()));
+                }
+            } else if node.is_text() {
+                let text = escape(&collapse(node.text().unwrap_or_default()));
+                if !text.is_empty() {
+                    blocks.push(format!("\n{text}"));
+                }
+            }
+        }
+
+        blocks.concat().trim_start().to_string()
+    }
+
+    /// Convert a CALS table. The rows in `<thead>` become the header row.
+    fn table(&mut self, node: Node) -> String {
+        let mut text = self.block_title(node);
+        text.push_str("|===\n");
+
+        for part in node.descendants() {
+            let is_header = part.has_tag_name("thead");
+            if !(is_header || part.has_tag_name("tbody")) {
+                continue;
+            }
+            for row in part.children().filter(|row| row.has_tag_name("row")) {
+                let cells = row
+                    .children()
+                    .filter(|entry| entry.has_tag_name("entry"))
+                    .map(|entry| {
+                        let cell = if entry.children().any(is_block) {
+                            let children: Vec<Node> = entry.children().collect();
+                            format!("a| {}", self.blocks(&children, 1))
+                        } else {
+                            format!("| {}", self.inline(entry).replace('|', "\\|"))
+                        };
+                        cell
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                text.push_str(&cells);
+                text.push('\n');
+                // A blank line after the first row marks it as the header.
+                if is_header {
+                    text.push('\n');
+                }
+            }
+        }
+
+        text.push_str("|===");
+        text
+    }
+
+    /// Format the title of a block as an AsciiDoc block title, if it has one.
+    fn block_title(&mut self, node: Node) -> String {
+        match child(node, "title") {
+            Some(title) => format!(".{}\n", self.inline(title)),
+            None => String::new(),
+        }
+    }
+
+    /// Convert the inline content of an element, such as a paragraph.
+    fn inline(&mut self, node: Node) -> String {
+        let mut text = String::new();
+
+        for child in node.children() {
+            if child.is_text() {
+                text.push_str(&escape(child.text().unwrap_or_default()));
+            } else if child.is_element() {
+                text.push_str(&self.inline_element(child));
+            }
+        }
+
+        collapse(&text)
+    }
+
+    /// Convert a single inline element.
+    fn inline_element(&mut self, node: Node) -> String {
+        let name = node.tag_name().name();
+        let content = self.inline(node);
+
+        match name {
+            "emphasis" => match node.attribute("role") {
+                Some("bold" | "strong") => format!("*{content}*"),
+                _ => format!("_{content}_"),
+            },
+            "literal" | "command" | "filename" | "option" | "parameter" | "varname"
+            | "systemitem" | "code" | "userinput" | "computeroutput" | "envar" | "function"
+            | "classname" | "package" => format!("`+{}+`", collapse(&verbatim(node))),
+            "replaceable" => format!("_<{content}>_"),
+            "guilabel" | "guibutton" | "guimenu" | "guimenuitem" | "keycap" => {
+                format!("*{content}*")
+            }
+            "ulink" => link(node.attribute("url").unwrap_or_default(), &content),
+            "link" => {
+                if let Some(target) = node.attribute("linkend") {
+                    xref(target, &content)
+                } else {
+                    let href = node
+                        .attributes()
+                        .find(|attribute| attribute.name() == "href")
+                        .map(|attribute| attribute.value())
+                        .unwrap_or_default();
+                    link(href, &content)
+                }
+            }
+            "xref" => xref(node.attribute("linkend").unwrap_or_default(), ""),
+            "quote" => format!("\"`{content}`\""),
+            "footnote" => format!("footnote:[{content}]"),
+            "indexterm" | "remark" => String::new(),
+            "inlinemediaobject" => node
+                .descendants()
+                .find(|image| image.has_tag_name("imagedata"))
+                .and_then(|image| image.attribute("fileref"))
+                .map(|file| format!("image:{file}[]"))
+                .unwrap_or_default(),
+            "phrase" | "citetitle" | "productname" | "firstterm" | "acronym" | "trademark" => {
+                content
+            }
+            _ => {
+                self.report(name);
+                content
+            }
+        }
+    }
+
+    /// Warn about an element that newdoc can't convert, once for each element name.
+    fn report(&mut self, name: &str) {
+        if self.reported.insert(name.to_string()) {
+            log::warn!("Unsupported DocBook element converted as plain text: <{name}>");
+        }
+    }
+}
+
+/// Find the first child element with this name.
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+/// The title of the element, either directly or in its `<info>` element.
+fn title_of(node: Node) -> Option<String> {
+    let title = child(node, "title")
+        .or_else(|| child(node, "info").and_then(|info| child(info, "title")))?;
+    let text: String = title
+        .descendants()
+        .filter(Node::is_text)
+        .filter_map(|node| node.text())
+        .collect();
+    // The module ID derives from the title, so only the attributes are escaped.
+    Some(escape_attributes(&collapse(&text)))
+}
+
+/// The ID of the element, from either the DocBook 4 `id` or the DocBook 5 `xml:id` attribute.
+fn id_of(node: Node) -> Option<String> {
+    node.attribute("id")
+        .or_else(|| node.attribute((NS_XML_URI, "id")))
+        .map(String::from)
+}
+
+/// Check if the node is a section element.
+fn is_section(node: Node) -> bool {
+    node.is_element() && SECTIONS.contains(&node.tag_name().name())
+}
+
+/// Check if the node is a block element rather than inline content.
+fn is_block(node: Node) -> bool {
+    node.is_element()
+        && matches!(
+            node.tag_name().name(),
+            "para"
+                | "simpara"
+                | "formalpara"
+                | "itemizedlist"
+                | "orderedlist"
+                | "variablelist"
+                | "procedure"
+                | "programlisting"
+                | "screen"
+                | "literallayout"
+                | "note"
+                | "tip"
+                | "important"
+                | "warning"
+                | "caution"
+                | "table"
+                | "informaltable"
+                | "figure"
+                | "mediaobject"
+        )
+}
+
+/// The text of a verbatim element, with all whitespace preserved.
+fn verbatim(node: Node) -> String {
+    node.descendants()
+        .filter(Node::is_text)
+        .filter_map(|node| node.text())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOK: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<article xmlns="http://docbook.org/ns/docbook" xmlns:xl="http://www.w3.org/1999/xlink" xml:id="my-article">
+  <title>My article</title>
+  <para>An introduction.</para>
+  <section xml:id="about">
+    <title>About the product</title>
+    <para>The {product} does <emphasis>many</emphasis> things * 2. See <xref linkend="installing"/>.</para>
+    <note><para>Read this.</para></note>
+  </section>
+  <section xml:id="installing">
+    <title>Installing the product</title>
+    <procedure>
+      <step><para>Run <command>install_all</command>.</para></step>
+      <step>
+        <para>Verify:</para>
+        <screen>$ verify</screen>
+      </step>
+    </procedure>
+  </section>
+  <section id="options">
+    <title>Options</title>
+    <informaltable>
+      <tgroup cols="2">
+        <thead><row><entry>Option</entry><entry>Meaning</entry></row></thead>
+        <tbody><row><entry><option>-v</option></entry><entry>Verbose</entry></row></tbody>
+      </tgroup>
+    </informaltable>
+  </section>
+</article>
+"#;
+
+    #[test]
+    fn convert_docbook() {
+        let document = parse(BOOK).expect("Failed to convert valid DocBook");
+
+        assert_eq!(document.title, "My article");
+        assert_eq!(document.id.as_deref(), Some("my-article"));
+        assert_eq!(document.preamble, "An introduction.");
+        assert_eq!(document.sections.len(), 3);
+
+        let about = &document.sections[0];
+        assert_eq!(about.id.as_deref(), Some("about"));
+        assert_eq!(about.mod_type, Some(ContentType::Concept));
+        assert_eq!(
+            about.body,
+            "The \\{product} does _many_ things {asterisk} 2. See <<installing>>.\n\n\
+            [NOTE]\n====\nRead this.\n===="
+        );
+
+        let installing = &document.sections[1];
+        assert_eq!(installing.mod_type, Some(ContentType::Procedure));
+        assert_eq!(
+            installing.body,
+            ".Procedure\n\n. Run `+install_all+`.\n. Verify:\n+\n----\n$ verify\n----"
+        );
+
+        let options = &document.sections[2];
+        assert_eq!(options.id.as_deref(), Some("options"));
+        assert_eq!(options.mod_type, Some(ContentType::Reference));
+        assert_eq!(
+            options.body,
+            "|===\n| Option | Meaning\n\n| `+-v+` | Verbose\n|==="
+        );
+    }
+
+    #[test]
+    fn detect_content_types() {
+        let book = r#"<article xmlns="http://docbook.org/ns/docbook">
+  <section><title>Steps</title><section><procedure><step><para>Run.</para></step></procedure></section></section>
+  <section><title>Table</title><table><title>Sizes</title></table></section>
+  <section><title>Variables</title><variablelist/></section>
+  <section><title>Command</title><refentry/></section>
+  <section><title>Prose</title><para>Text.</para><itemizedlist/></section>
+</article>"#;
+        let document = roxmltree::Document::parse(book).expect("Invalid XML");
+        let types: Vec<ContentType> = document
+            .root_element()
+            .children()
+            .filter(|node| node.has_tag_name("section"))
+            .map(content_type)
+            .collect();
+
+        assert_eq!(
+            types,
+            vec![
+                ContentType::Procedure,
+                ContentType::Reference,
+                ContentType::Reference,
+                ContentType::Reference,
+                ContentType::Concept,
+            ]
+        );
+    }
+}
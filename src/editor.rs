@@ -0,0 +1,187 @@
+/*
+newdoc: Generate pre-populated documentation modules formatted with AsciiDoc.
+Copyright (C) 2026  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! # `editor.rs`
+//!
+//! This module opens the generated files in the user's text editor.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+
+use crate::Options;
+
+/// Editors that accept the `+LINE` argument to place the cursor on a line.
+const PLUS_LINE_EDITORS: [&str; 12] = [
+    "vi",
+    "vim",
+    "nvim",
+    "gvim",
+    "view",
+    "nano",
+    "emacs",
+    "emacsclient",
+    "micro",
+    "kak",
+    "joe",
+    "mcedit",
+];
+
+/// Open each file in the editor, one after another.
+/// The cursor starts on the abstract line, if the file has one and the editor supports it.
+///
+/// # Errors
+///
+/// Fails if no editor is configured or if the editor cannot start.
+pub fn open(files: &[(PathBuf, &str)], options: &Options) -> Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let command = editor_command(options)?;
+
+    for (path, text) in files {
+        let mut editor = build_command(&command, path, text)?;
+        let program = editor.get_program().to_string_lossy().to_string();
+
+        log::debug!("Running the editor: {editor:?}");
+
+        let status = editor
+            .status()
+            .wrap_err_with(|| eyre!("Failed to start the `{program}` editor."))?;
+        if !status.success() {
+            log::warn!(
+                "The editor exited with {status} when editing {}.",
+                path.display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The command that opens the file in the editor, with the cursor on the abstract line
+/// if the editor supports the `+LINE` argument. The command is split into words like in a shell,
+/// so that a quoted program path can contain spaces.
+fn build_command(command: &str, path: &Path, text: &str) -> Result<Command> {
+    let words = shell_words::split(command)
+        .wrap_err_with(|| eyre!("Failed to parse the `{command}` editor command."))?;
+    let Some((program, arguments)) = words.split_first() else {
+        bail!("The editor command is empty.");
+    };
+
+    let mut editor = Command::new(program);
+    editor.args(arguments);
+    if let Some(line) = abstract_line(text).filter(|_| supports_plus_line(program)) {
+        editor.arg(format!("+{line}"));
+    }
+    editor.arg(path);
+
+    Ok(editor)
+}
+
+/// The editor from the configuration, or from the `VISUAL` or `EDITOR` environment variables.
+fn editor_command(options: &Options) -> Result<String> {
+    let configured = options.editor.clone().filter(|editor| !editor.is_empty());
+    let from_env = || {
+        ["VISUAL", "EDITOR"]
+            .iter()
+            .filter_map(|variable| env::var(variable).ok())
+            .find(|editor| !editor.trim().is_empty())
+    };
+
+    match configured.or_else(from_env) {
+        Some(editor) => Ok(editor),
+        None => bail!(
+            "No editor is set. Set the `editor` key in the configuration file, \
+            or the VISUAL or EDITOR environment variable."
+        ),
+    }
+}
+
+/// Check if the editor program, such as `/usr/bin/vim`, accepts the `+LINE` argument.
+fn supports_plus_line(program: &str) -> bool {
+    Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| PLUS_LINE_EDITORS.contains(&name))
+}
+
+/// The line number of the abstract, which follows the `[role="_abstract"]` line.
+/// The first line is number 1.
+fn abstract_line(text: &str) -> Option<usize> {
+    text.lines()
+        .position(|line| line.trim() == r#"[role="_abstract"]"#)
+        .map(|index| index + 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_on_abstract() {
+        let text = "= A title\n\n[role=\"_abstract\"]\nThe abstract.\n";
+        assert_eq!(abstract_line(text), Some(4));
+        assert_eq!(abstract_line("= A snippet\n"), None);
+
+        assert!(supports_plus_line("/usr/bin/vim"));
+        assert!(supports_plus_line("nano"));
+        assert!(!supports_plus_line("code"));
+    }
+
+    /// The arguments of the command, as strings.
+    fn arguments(command: &Command) -> Vec<String> {
+        command
+            .get_args()
+            .map(|argument| argument.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn editor_commands() {
+        let path = Path::new("modules/con_a-title.adoc");
+        let text = "= A title\n\n[role=\"_abstract\"]\nThe abstract.\n";
+
+        let vim =
+            build_command("/usr/bin/vim -p", path, text).expect("Failed to build the command");
+        assert_eq!(vim.get_program(), "/usr/bin/vim");
+        assert_eq!(arguments(&vim), ["-p", "+4", "modules/con_a-title.adoc"]);
+
+        let code = build_command("code --wait", path, text).expect("Failed to build the command");
+        assert_eq!(code.get_program(), "code");
+        assert_eq!(arguments(&code), ["--wait", "modules/con_a-title.adoc"]);
+
+        let snippet =
+            build_command("nano", path, "= A snippet\n").expect("Failed to build the command");
+        assert_eq!(arguments(&snippet), ["modules/con_a-title.adoc"]);
+
+        let quoted = build_command(r#""/opt/My Editor/edit" --title 'A title'"#, path, text)
+            .expect("Failed to build the command");
+        assert_eq!(quoted.get_program(), "/opt/My Editor/edit");
+        assert_eq!(
+            arguments(&quoted),
+            ["--title", "A title", "modules/con_a-title.adoc"]
+        );
+
+        assert!(build_command("  ", path, text).is_err());
+        assert!(build_command("vim 'unclosed", path, text).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+/*
+newdoc: Generate pre-populated documentation modules formatted with AsciiDoc.
+Copyright (C) 2026  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! # `existing.rs`
+//!
+//! This module reads files that already exist in the repository,
+//! so that a populated assembly can include them next to the newly generated modules.
+
+use std::fs;
+use std::path::Path;
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+
+use crate::module::{include_statement, ContentType};
+
+/// The attribute that declares the content type in a modular file.
+const CONTENT_TYPE_ATTRIBUTE: &str = ":_mod-docs-content-type:";
+
+/// The older name of the content type attribute, which existing files might still use.
+const LEGACY_CONTENT_TYPE_ATTRIBUTE: &str = ":_content-type:";
+
+/// Read an existing module or assembly file and prepare an include statement for it.
+pub fn include_statement_for(path: &Path) -> Result<String> {
+    let text = fs::read_to_string(path)
+        .wrap_err_with(|| eyre!("Failed to read the existing file `{}`.", path.display()))?;
+
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| eyre!("The path `{}` is not a valid file name.", path.display()))?;
+
+    let Some(mod_type) = detect_content_type(&text, file_name) else {
+        bail!(
+            "Cannot detect the content type of the existing file `{}`. \
+            Add the `{CONTENT_TYPE_ATTRIBUTE}` attribute to the file, \
+            or use a file name prefix such as `proc_`.",
+            path.display()
+        );
+    };
+
+    // The include path depends on the directory that holds the file,
+    // exactly as with a newly generated file in the target directory.
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let include_statement = include_statement(mod_type, directory, file_name);
+
+    log::debug!(
+        "Existing {mod_type} file `{}`: {include_statement}",
+        path.display()
+    );
+
+    Ok(include_statement)
+}
+
+/// Detect the content type of an existing file, preferably from its content type attribute,
+/// or from its file name prefix.
+pub fn detect_content_type(text: &str, file_name: &str) -> Option<ContentType> {
+    content_type_from_attribute(text).or_else(|| content_type_from_prefix(file_name))
+}
+
+/// Detect the content type from the `:_mod-docs-content-type:` attribute,
+/// or from the legacy `:_content-type:` attribute, if the file sets one of them.
+fn content_type_from_attribute(text: &str) -> Option<ContentType> {
+    text.lines()
+        .find_map(|line| {
+            let line = line.trim();
+            line.strip_prefix(CONTENT_TYPE_ATTRIBUTE)
+                .or_else(|| line.strip_prefix(LEGACY_CONTENT_TYPE_ATTRIBUTE))
+        })
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Detect the content type from the file name prefix, such as `proc_`.
+/// Accepts the older convention with a dash, such as `proc-`, as well.
+fn content_type_from_prefix(file_name: &str) -> Option<ContentType> {
+    ContentType::ALL.into_iter().find(|mod_type| {
+        let prefix = mod_type.prefix();
+        let dash_prefix = prefix.replace('_', "-");
+        file_name.starts_with(prefix) || file_name.starts_with(&dash_prefix)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_type_detection() {
+        let text = ":_newdoc-version: 2.18.7\n:_mod-docs-content-type: REFERENCE\n\n= Title\n";
+        assert_eq!(
+            content_type_from_attribute(text),
+            Some(ContentType::Reference)
+        );
+        assert_eq!(
+            content_type_from_attribute(":_content-type: PROCEDURE\n\n= Title\n"),
+            Some(ContentType::Procedure)
+        );
+        assert_eq!(content_type_from_attribute("= Title\n"), None);
+
+        assert_eq!(
+            content_type_from_prefix("proc_installing.adoc"),
+            Some(ContentType::Procedure)
+        );
+        assert_eq!(
+            content_type_from_prefix("assembly-upgrading.adoc"),
+            Some(ContentType::Assembly)
+        );
+        assert_eq!(content_type_from_prefix("installing.adoc"), None);
+
+        // A file without a prefix gets its type from the attribute.
+        assert_eq!(
+            detect_content_type(":_content-type: CONCEPT\n", "installing.adoc"),
+            Some(ContentType::Concept)
+        );
+    }
+}
@@ -0,0 +1,194 @@
+/*
+newdoc: Generate pre-populated documentation modules formatted with AsciiDoc.
+Copyright (C) 2026  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! # `git.rs`
+//!
+//! This module stages the written files in their Git repository and optionally
+//! commits them, using the local `git` binary. It never contacts a remote.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+
+use crate::module::absolute_path;
+use crate::Options;
+
+/// Stage the files in the repositories that contain them, and commit them
+/// if the options set a commit message.
+///
+/// # Errors
+///
+/// Fails if a file isn't in a Git repository or if a `git` command fails.
+pub fn add_and_commit(files: &[PathBuf], options: &Options) -> Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    for (root, paths) in group_by_repository(files)? {
+        let mut add = vec!["add", "--"];
+        add.extend(paths.iter().map(String::as_str));
+        run_git(&root, &add)?;
+        for path in &paths {
+            log::info!("‣ Staged in Git: {path}");
+        }
+
+        if let Some(message) = &options.git_commit {
+            // Commit only the generated files, not other changes that the user has staged.
+            let mut commit = vec!["commit", "--message", message.as_str(), "--"];
+            commit.extend(paths.iter().map(String::as_str));
+            run_git(&root, &commit)?;
+            log::info!("‣ Committed in Git: {}", root.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Sort the files by the root of their Git repository.
+/// The paths are relative to the root, so that `git` accepts them from there.
+fn group_by_repository(files: &[PathBuf]) -> Result<BTreeMap<PathBuf, Vec<String>>> {
+    let mut repositories: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+
+    for file in files {
+        let absolute = absolute_path(file)
+            .ok_or_else(|| eyre!("Failed to access the `{}` file.", file.display()))?;
+        let dir = absolute
+            .parent()
+            .ok_or_else(|| eyre!("The `{}` file has no directory.", file.display()))?;
+
+        let root = run_git(dir, &["rev-parse", "--show-toplevel"])
+            .wrap_err_with(|| eyre!("The `{}` file isn't in a Git repository.", file.display()))?;
+        let root = PathBuf::from(root.trim());
+        let root = root.canonicalize().unwrap_or(root);
+
+        let relative = absolute.strip_prefix(&root).wrap_err_with(|| {
+            eyre!(
+                "The `{}` file is outside of its repository, `{}`.",
+                file.display(),
+                root.display()
+            )
+        })?;
+
+        repositories
+            .entry(root.clone())
+            .or_default()
+            .push(relative.to_string_lossy().into_owned());
+    }
+
+    Ok(repositories)
+}
+
+/// Run a `git` command in the directory and return its standard output.
+fn run_git(dir: &Path, arguments: &[&str]) -> Result<String> {
+    log::debug!("Running git {} in {}", arguments.join(" "), dir.display());
+
+    let output = Command::new("git")
+        .args(arguments)
+        .current_dir(dir)
+        .output()
+        .wrap_err("Failed to run the `git` command. Is Git installed?")?;
+
+    if !output.status.success() {
+        bail!(
+            "The `git {}` command failed:\n{}",
+            arguments[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Initialize an empty repository with a `modules` directory.
+    fn repository() -> tempfile::TempDir {
+        let temp = tempfile::tempdir().expect("Failed to create a temporary directory");
+        let repo = temp.path();
+        fs::create_dir(repo.join("modules")).expect("Failed to create a directory");
+        run_git(repo, &["init", "--quiet"]).expect("Failed to initialize a repository");
+        run_git(repo, &["config", "user.name", "Test User"]).expect("Failed to configure Git");
+        run_git(repo, &["config", "user.email", "test@example.com"])
+            .expect("Failed to configure Git");
+
+        temp
+    }
+
+    #[test]
+    fn stage_in_repository() {
+        let temp = repository();
+        let repo = temp.path();
+
+        let file = repo.join("modules/con_staged.adoc");
+        fs::write(&file, "= Staged\n").expect("Failed to write a file");
+
+        let options = Options {
+            git_add: true,
+            ..Default::default()
+        };
+        add_and_commit(&[file], &options).expect("Failed to stage a file");
+
+        let staged = run_git(repo, &["diff", "--cached", "--name-only"])
+            .expect("Failed to list the staged files");
+        assert_eq!(staged.trim(), "modules/con_staged.adoc");
+    }
+
+    #[test]
+    fn commit_in_repository() {
+        let temp = repository();
+        let repo = temp.path();
+
+        // A change that the user staged before doesn't go into the commit.
+        fs::write(repo.join("unrelated.adoc"), "= Unrelated\n").expect("Failed to write a file");
+        run_git(repo, &["add", "unrelated.adoc"]).expect("Failed to stage a file");
+
+        let files = [
+            repo.join("modules/con_first.adoc"),
+            repo.join("modules/proc_second.adoc"),
+        ];
+        for file in &files {
+            fs::write(file, "= Generated\n").expect("Failed to write a file");
+        }
+
+        let options = Options {
+            git_add: true,
+            git_commit: Some("Add new modules".to_string()),
+            ..Default::default()
+        };
+        add_and_commit(&files, &options).expect("Failed to commit the files");
+
+        let log = run_git(repo, &["log", "--format=%s"]).expect("Failed to read the log");
+        assert_eq!(log.trim(), "Add new modules");
+
+        let committed = run_git(repo, &["show", "--name-only", "--format=", "HEAD"])
+            .expect("Failed to list the committed files");
+        assert_eq!(
+            committed.lines().collect::<Vec<_>>(),
+            ["modules/con_first.adoc", "modules/proc_second.adoc"]
+        );
+
+        let staged = run_git(repo, &["diff", "--cached", "--name-only"])
+            .expect("Failed to list the staged files");
+        assert_eq!(staged.trim(), "unrelated.adoc");
+    }
+}
@@ -0,0 +1,573 @@
+/*
+newdoc: Generate pre-populated documentation modules formatted with AsciiDoc.
+Copyright (C) 2026  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! # `groups.rs`
+//!
+//! This module assigns the files requested on the command line
+//! to the populated assemblies that include them.
+
+use std::path::PathBuf;
+
+use color_eyre::eyre::{bail, Result};
+
+use crate::cmd_line::ActionItem;
+use crate::module::{is_valid_id, ContentType, Input};
+
+/// A module that the user requested on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requested {
+    pub mod_type: ContentType,
+    pub title: String,
+    pub overrides: Overrides,
+}
+
+/// The ID and the file name that the user set for a title,
+/// instead of the ones that newdoc derives from the title.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Overrides {
+    pub id: Option<String>,
+    pub file_name: Option<String>,
+}
+
+/// A file that a populated assembly includes.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Member {
+    /// The position of a newly generated module in `Grouping::modules`
+    Module(usize),
+    /// A file that already exists on the disk
+    Existing(PathBuf),
+}
+
+/// A populated assembly and the files that it includes.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Group {
+    pub title: String,
+    pub overrides: Overrides,
+    pub members: Vec<Member>,
+}
+
+/// All requested files, sorted into the modules and the populated assemblies.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Grouping {
+    /// All modules except for the populated assemblies
+    pub modules: Vec<Requested>,
+    pub groups: Vec<Group>,
+}
+
+impl ActionItem {
+    /// Split the item into its content type and title.
+    /// Returns `None` for the `--include-in` and `--include-existing` options,
+    /// which don't request a new module.
+    fn requested(&self) -> Option<Requested> {
+        let (mod_type, title) = match self {
+            Self::Assembly { assembly } => (ContentType::Assembly, assembly),
+            Self::Concept { concept } => (ContentType::Concept, concept),
+            Self::Procedure { procedure } => (ContentType::Procedure, procedure),
+            Self::Reference { reference } => (ContentType::Reference, reference),
+            Self::Snippet { snippet } => (ContentType::Snippet, snippet),
+            Self::IncludeIn { .. }
+            | Self::IncludeExisting { .. }
+            | Self::Id { .. }
+            | Self::FileName { .. } => return None,
+        };
+
+        Some(Requested {
+            mod_type,
+            title: title.clone(),
+            overrides: Overrides::default(),
+        })
+    }
+}
+
+impl Overrides {
+    /// Check that the ID and the file name are usable.
+    pub fn check(&self) -> Result<()> {
+        if let Some(id) = &self.id {
+            if !is_valid_id(id) {
+                bail!(
+                    "The `{id}` ID can only contain ASCII letters, digits, dashes, underscores, and periods."
+                );
+            }
+        }
+        if let Some(file_name) = &self.file_name {
+            if file_name.is_empty() || file_name.contains(['/', '\\']) {
+                bail!(
+                    "The `{file_name}` file name must not be empty or contain a directory. \
+                    Use the --target-dir option to set the directory."
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Use the ID and the file name instead of the ones derived from the title.
+    #[must_use]
+    pub fn apply(&self, mut input: Input) -> Input {
+        if let Some(id) = &self.id {
+            input = input.with_id(id);
+        }
+        if let Some(file_name) = &self.file_name {
+            input = input.with_file_name(file_name);
+        }
+
+        input
+    }
+}
+
+/// The last title on the command line, which the `--id` and `--file-name` options apply to.
+#[derive(Clone, Copy)]
+enum Last {
+    None,
+    Module(usize),
+    Group(usize),
+}
+
+/// Sort the command-line items into modules and populated assemblies.
+///
+/// With a single `--include-in` option, the populated assembly includes all modules,
+/// regardless of where they appear on the command line. With several `--include-in` options,
+/// each populated assembly includes the modules that follow it, up to the next `--include-in`.
+///
+/// The populated assemblies list their includes in the command-line order,
+/// except for the files named in `order`, which come first, in that order.
+pub fn assign(items: &[ActionItem], order: &[String]) -> Result<Grouping> {
+    let group_count = items
+        .iter()
+        .filter(|item| matches!(item, ActionItem::IncludeIn { .. }))
+        .count();
+
+    let mut modules: Vec<Requested> = Vec::new();
+    let mut existing: Vec<PathBuf> = Vec::new();
+    let mut groups: Vec<Group> = Vec::new();
+    // All included files in the command-line order, for the case of a single group
+    let mut all_members: Vec<Member> = Vec::new();
+    let mut last = Last::None;
+
+    for item in items {
+        let member = match item {
+            ActionItem::IncludeIn { include_in } => {
+                groups.push(Group {
+                    title: include_in.clone(),
+                    overrides: Overrides::default(),
+                    members: Vec::new(),
+                });
+                last = Last::Group(groups.len() - 1);
+                continue;
+            }
+            ActionItem::Id { .. } | ActionItem::FileName { .. } => {
+                set_override(item, last, &mut modules, &mut groups)?;
+                continue;
+            }
+            ActionItem::IncludeExisting { include_existing } => {
+                if group_count == 0 {
+                    bail!(
+                        "The existing file `{}` can only be included in a populated assembly. \
+                        Add the --include-in option.",
+                        include_existing.display()
+                    );
+                }
+                if existing.contains(include_existing) {
+                    bail!(
+                        "The existing file `{}` is listed more than once. \
+                        Specify each file only once.",
+                        include_existing.display()
+                    );
+                }
+                existing.push(include_existing.clone());
+                last = Last::None;
+
+                Member::Existing(include_existing.clone())
+            }
+            // All other items are regular modules.
+            _ => {
+                let Some(requested) = item.requested() else {
+                    continue;
+                };
+
+                // Each module can only appear once, or newdoc would overwrite it with itself.
+                if modules.iter().any(|module| {
+                    module.mod_type == requested.mod_type && module.title == requested.title
+                }) {
+                    bail!(
+                        "The {} `{}` is listed more than once. Specify each module only once.",
+                        requested.mod_type,
+                        requested.title
+                    );
+                }
+
+                modules.push(requested);
+                last = Last::Module(modules.len() - 1);
+
+                Member::Module(modules.len() - 1)
+            }
+        };
+
+        // A single populated assembly includes everything, including the files
+        // that precede it. This is the original behavior of `--include-in`.
+        // With several populated assemblies, the file belongs to the preceding one.
+        if group_count == 1 {
+            all_members.push(member);
+        } else if group_count > 1 {
+            if let Some(group) = groups.last_mut() {
+                group.members.push(member);
+            } else {
+                bail!(
+                    "The {} appears before the first --include-in option, \
+                    so it belongs to no populated assembly. \
+                    Move it after the --include-in option of the assembly that should include it.",
+                    describe(&member, &modules)
+                );
+            }
+        }
+    }
+
+    if group_count == 1 {
+        if let Some(group) = groups.first_mut() {
+            group.members = all_members;
+        }
+    }
+
+    for group in &groups {
+        // An empty group almost certainly means that the modules are in the wrong place
+        // on the command line.
+        if group.members.is_empty() {
+            bail!(
+                "The populated assembly `{}` includes no modules. \
+                Specify its modules after its --include-in option.",
+                group.title
+            );
+        }
+    }
+
+    apply_order(&mut groups, &modules, order)?;
+
+    Ok(Grouping { modules, groups })
+}
+
+/// Set the ID or the file name for the last title on the command line.
+fn set_override(
+    item: &ActionItem,
+    last: Last,
+    modules: &mut [Requested],
+    groups: &mut [Group],
+) -> Result<()> {
+    let option = match item {
+        ActionItem::FileName { .. } => "--file-name",
+        _ => "--id",
+    };
+    let overrides = match last {
+        Last::Module(position) => &mut modules[position].overrides,
+        Last::Group(position) => &mut groups[position].overrides,
+        Last::None => bail!(
+            "The {option} option applies to the title that precedes it, \
+            such as --procedure \"Installing the product\" {option} VALUE. \
+            Move it after the title of the file."
+        ),
+    };
+
+    match item {
+        ActionItem::Id { id } => {
+            if overrides.id.is_some() {
+                bail!("The --id `{id}` option repeats an ID for the same title.");
+            }
+            overrides.id = Some(id.clone());
+        }
+        ActionItem::FileName { file_name } => {
+            if overrides.file_name.is_some() {
+                bail!(
+                    "The --file-name `{file_name}` option repeats a file name for the same title."
+                );
+            }
+            overrides.file_name = Some(file_name.clone());
+        }
+        _ => {}
+    }
+
+    overrides.check()
+}
+
+/// Move the files named in `order` to the start of their populated assemblies.
+/// The remaining files keep their command-line order.
+fn apply_order(groups: &mut [Group], modules: &[Requested], order: &[String]) -> Result<()> {
+    if !order.is_empty() && groups.is_empty() {
+        bail!(
+            "The --order option only applies to a populated assembly. Add the --include-in option."
+        );
+    }
+
+    for name in order {
+        let mut is_included = false;
+
+        for group in groups.iter() {
+            let matches: Vec<&Member> = group
+                .members
+                .iter()
+                .filter(|member| is_named(member, modules, name))
+                .collect();
+
+            // Several files with the same title, such as a concept and a procedure,
+            // would otherwise silently take the position in turn.
+            if let [first, second, ..] = matches[..] {
+                bail!(
+                    "The --order option lists `{name}`, which matches both the {} and the {}. \
+                    Set a file name for the module with the --file-name option, \
+                    and list the file name instead.",
+                    describe(first, modules),
+                    describe(second, modules)
+                );
+            }
+
+            is_included |= !matches.is_empty();
+        }
+
+        if !is_included {
+            bail!(
+                "The --order option lists `{name}`, which is not included in any populated assembly."
+            );
+        }
+    }
+
+    // The position of the file in the `order` list, or `None` for unlisted files.
+    // The sort is stable, so the unlisted files keep their command-line order.
+    let rank = |member: &Member| {
+        let position = order
+            .iter()
+            .position(|name| is_named(member, modules, name));
+        (position.is_none(), position)
+    };
+
+    for group in groups {
+        group.members.sort_by_key(rank);
+    }
+
+    Ok(())
+}
+
+/// Check if the `--order` option refers to this group member,
+/// either by the module title, by the file name that the `--file-name` option sets,
+/// or by the path to the existing file.
+fn is_named(member: &Member, modules: &[Requested], name: &str) -> bool {
+    match member {
+        Member::Module(position) => {
+            let module = &modules[*position];
+            // The file name matches with or without the `.adoc` extension.
+            let without_extension = |file_name: &str| {
+                file_name
+                    .strip_suffix(".adoc")
+                    .unwrap_or(file_name)
+                    .to_string()
+            };
+            module.title == name
+                || module
+                    .overrides
+                    .file_name
+                    .as_deref()
+                    .is_some_and(|file_name| {
+                        without_extension(file_name) == without_extension(name)
+                    })
+        }
+        Member::Existing(path) => path.as_os_str() == name,
+    }
+}
+
+/// Describe the group member for an error message.
+fn describe(member: &Member, modules: &[Requested]) -> String {
+    match member {
+        Member::Module(position) => {
+            let module = &modules[*position];
+            format!("{} `{}`", module.mod_type, module.title)
+        }
+        Member::Existing(path) => format!("existing file `{}`", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn concept(title: &str) -> ActionItem {
+        ActionItem::Concept {
+            concept: title.to_string(),
+        }
+    }
+
+    fn procedure(title: &str) -> ActionItem {
+        ActionItem::Procedure {
+            procedure: title.to_string(),
+        }
+    }
+
+    fn include_in(title: &str) -> ActionItem {
+        ActionItem::IncludeIn {
+            include_in: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn single_group_includes_everything() {
+        let items = [
+            concept("First"),
+            include_in("Assembly"),
+            procedure("Second"),
+        ];
+        let grouping = assign(&items, &[]).expect("Failed to assign valid items");
+
+        assert_eq!(grouping.modules.len(), 2);
+        assert_eq!(
+            grouping.groups,
+            vec![Group {
+                title: "Assembly".to_string(),
+                overrides: Overrides::default(),
+                members: vec![Member::Module(0), Member::Module(1)],
+            }]
+        );
+    }
+
+    #[test]
+    fn several_groups_follow_positions() {
+        let items = [
+            include_in("First assembly"),
+            procedure("A procedure"),
+            concept("A concept"),
+            include_in("Second assembly"),
+            concept("Another concept"),
+        ];
+        let grouping = assign(&items, &[]).expect("Failed to assign valid items");
+
+        assert_eq!(
+            grouping.groups[0].members,
+            vec![Member::Module(0), Member::Module(1)]
+        );
+        assert_eq!(grouping.groups[1].members, vec![Member::Module(2)]);
+    }
+
+    #[test]
+    fn explicit_order_comes_first() {
+        let items = [
+            include_in("Assembly"),
+            concept("First"),
+            procedure("Second"),
+            concept("Third"),
+        ];
+        let order = ["Third".to_string(), "First".to_string()];
+        let grouping = assign(&items, &order).expect("Failed to assign valid items");
+
+        assert_eq!(
+            grouping.groups[0].members,
+            vec![Member::Module(2), Member::Module(0), Member::Module(1)]
+        );
+
+        let order = ["Missing".to_string()];
+        assert!(assign(&items, &order).is_err());
+    }
+
+    #[test]
+    fn ambiguous_order_is_rejected() {
+        let items = [
+            include_in("Assembly"),
+            concept("Installing"),
+            procedure("Installing"),
+            ActionItem::FileName {
+                file_name: "proc_install".to_string(),
+            },
+        ];
+
+        let order = ["Installing".to_string()];
+        let error = assign(&items, &order).expect_err("The ambiguous name wasn't detected");
+        assert!(error.to_string().contains("--file-name"));
+
+        // The file name picks the procedure.
+        let order = ["proc_install.adoc".to_string()];
+        let grouping = assign(&items, &order).expect("Failed to assign valid items");
+        assert_eq!(
+            grouping.groups[0].members,
+            vec![Member::Module(1), Member::Module(0)]
+        );
+    }
+
+    #[test]
+    fn misplaced_modules_are_rejected() {
+        // A module before the first of several groups
+        let items = [
+            concept("Orphan"),
+            include_in("First assembly"),
+            procedure("A procedure"),
+            include_in("Second assembly"),
+            concept("A concept"),
+        ];
+        assert!(assign(&items, &[]).is_err());
+
+        // A group without modules
+        let items = [
+            include_in("First assembly"),
+            include_in("Second assembly"),
+            concept("A concept"),
+        ];
+        assert!(assign(&items, &[]).is_err());
+
+        // The same module in two groups
+        let items = [
+            include_in("First assembly"),
+            concept("A concept"),
+            include_in("Second assembly"),
+            concept("A concept"),
+        ];
+        assert!(assign(&items, &[]).is_err());
+    }
+
+    #[test]
+    fn overrides_follow_titles() {
+        let id = |id: &str| ActionItem::Id { id: id.to_string() };
+        let file_name = |file_name: &str| ActionItem::FileName {
+            file_name: file_name.to_string(),
+        };
+
+        let items = [
+            include_in("An assembly"),
+            id("legacy-assembly"),
+            procedure("Installing {product}"),
+            id("installing-the-product"),
+            file_name("proc_install.adoc"),
+            concept("A concept"),
+        ];
+        let grouping = assign(&items, &[]).expect("Failed to assign valid items");
+
+        assert_eq!(
+            grouping.groups[0].overrides.id.as_deref(),
+            Some("legacy-assembly")
+        );
+        assert_eq!(
+            grouping.modules[0].overrides,
+            Overrides {
+                id: Some("installing-the-product".to_string()),
+                file_name: Some("proc_install.adoc".to_string()),
+            }
+        );
+        assert_eq!(grouping.modules[1].overrides, Overrides::default());
+
+        // An ID before any title
+        assert!(assign(&[id("orphan"), concept("A concept")], &[]).is_err());
+        // Two IDs for one title
+        assert!(assign(&[concept("A concept"), id("one"), id("two")], &[]).is_err());
+        // An ID with an attribute
+        assert!(assign(&[concept("A concept"), id("{product}-intro")], &[]).is_err());
+        // A file name with a directory
+        assert!(assign(&[concept("A concept"), file_name("a/b.adoc")], &[]).is_err());
+    }
+}
@@ -0,0 +1,611 @@
+/*
+newdoc: Generate pre-populated documentation modules formatted with AsciiDoc.
+Copyright (C) 2026  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! # `html.rs`
+//!
+//! This module converts an HTML page, such as a wiki page export, to AsciiDoc blocks,
+//! which the `convert` module then splits into modules, usually at the `<h2>` headings.
+
+use std::collections::BTreeMap;
+
+use color_eyre::eyre::Result;
+use scraper::{ElementRef, Html};
+
+use crate::convert::{collapse, escape, escape_attributes, link, Block, Document};
+
+/// The admonition labels, which HTML pages often set as a class of a `<div>` element.
+const ADMONITIONS: [&str; 5] = ["note", "tip", "important", "warning", "caution"];
+
+/// Elements that carry no content for the documentation. newdoc skips them and reports them.
+const SKIPPED: [&str; 11] = [
+    "script", "style", "noscript", "iframe", "form", "input", "button", "select", "video", "audio",
+    "svg",
+];
+
+/// Parse the HTML text and convert it to a document split into sections.
+pub fn parse(text: &str) -> Result<Document> {
+    let html = Html::parse_document(text);
+    let root = html.root_element();
+
+    let mut converter = Converter::default();
+    if let Some(body) = find(root, "body") {
+        converter.container(body);
+    }
+    converter.report();
+
+    let blocks = converter.finish();
+
+    // Without a level 1 heading, the page title becomes the title.
+    let has_h1 = blocks
+        .iter()
+        .any(|block| matches!(block, Block::Heading { level: 1, .. }));
+    let title = if has_h1 {
+        None
+    } else {
+        find(root, "title")
+            .map(|title| escape_attributes(&collapse(&title.text().collect::<String>())))
+            .filter(|title| !title.is_empty())
+    };
+
+    Document::from_blocks(blocks, title)
+}
+
+/// The state of the conversion from HTML elements to AsciiDoc.
+#[derive(Debug, Default)]
+struct Converter {
+    blocks: Vec<Block>,
+    /// The content after the last heading
+    current: String,
+    /// The number of occurrences of each unsupported element
+    unsupported: BTreeMap<String, usize>,
+    /// Whether the converter is in a heading, which only escapes the attributes in the text
+    in_heading: bool,
+}
+
+impl Converter {
+    /// Convert the children of an element that can contain both blocks and inline content.
+    /// Inline content between the blocks becomes a paragraph.
+    fn container(&mut self, element: ElementRef) {
+        let mut paragraph = String::new();
+
+        for node in element.children() {
+            if let Some(text) = node.value().as_text() {
+                paragraph.push_str(&self.text(text));
+            } else if let Some(child) = ElementRef::wrap(node) {
+                if is_block(child) {
+                    self.paragraph(&paragraph);
+                    paragraph.clear();
+                    self.block(child);
+                } else {
+                    paragraph.push_str(&self.inline_element(child));
+                }
+            }
+        }
+
+        self.paragraph(&paragraph);
+    }
+
+    /// Add a finished block, followed by a blank line.
+    fn push_block(&mut self, text: &str) {
+        self.current.push_str(text);
+        self.current.push_str("\n\n");
+    }
+
+    /// Add a paragraph of inline content, unless it's blank.
+    fn paragraph(&mut self, text: &str) {
+        let text = tidy(text);
+        if !text.is_empty() {
+            self.push_block(&text);
+        }
+    }
+
+    /// Convert a single block element.
+    fn block(&mut self, element: ElementRef) {
+        let name = element.value().name();
+
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let content = std::mem::take(&mut self.current);
+                if !content.trim().is_empty() {
+                    self.blocks.push(Block::Content(content));
+                }
+                let level = name[1..].parse().unwrap_or(1);
+                self.in_heading = true;
+                let title = tidy(&self.inline(element));
+                self.in_heading = false;
+                self.blocks.push(Block::Heading {
+                    level,
+                    title,
+                    id: element.attr("id").map(String::from),
+                });
+            }
+            "p" => {
+                let text = self.inline(element);
+                self.paragraph(&text);
+            }
+            "ul" | "ol" => {
+                let list = self.list(element, 1);
+                self.push_block(&list);
+            }
+            "pre" => {
+                let text = pre(element);
+                self.push_block(&text);
+            }
+            "table" => {
+                let text = self.table(element);
+                self.push_block(&text);
+            }
+            "blockquote" => {
+                let text = self.nested(element);
+                self.push_block(&format!("____\n{text}\n____"));
+            }
+            "hr" => self.push_block("'''"),
+            "dl" => {
+                let text = self.definition_list(element);
+                self.push_block(&text);
+            }
+            "img" => {
+                let image = image(element);
+                self.push_block(&format!("image:{image}"));
+            }
+            "div" | "aside" => {
+                let label = admonition(element);
+                if let Some(label) = label {
+                    let text = self.nested(element);
+                    self.push_block(&format!("[{label}]\n====\n{text}\n===="));
+                } else {
+                    self.container(element);
+                }
+            }
+            _ if SKIPPED.contains(&name) => self.unsupported(name),
+            // Structural elements that only group other content
+            _ => self.container(element),
+        }
+    }
+
+    /// Convert the content of an element to a separate piece of text,
+    /// such as the content of a quote or an admonition.
+    fn nested(&mut self, element: ElementRef) -> String {
+        let outer = std::mem::take(&mut self.current);
+        let blocks_before = self.blocks.len();
+        self.container(element);
+        // Headings inside of the nested content can't start sections. They become block titles.
+        let mut inner = String::new();
+        for block in self.blocks.drain(blocks_before..) {
+            match block {
+                Block::Heading { title, .. } => {
+                    inner.push('.');
+                    inner.push_str(&title);
+                    inner.push('\n');
+                }
+                Block::Content(content) => inner.push_str(&content),
+            }
+        }
+        inner.push_str(&std::mem::replace(&mut self.current, outer));
+        inner.trim().to_string()
+    }
+
+    /// Convert a list. Nested lists repeat the marker character.
+    fn list(&mut self, element: ElementRef, depth: usize) -> String {
+        let marker = if element.value().name() == "ol" {
+            ".".repeat(depth)
+        } else {
+            "*".repeat(depth)
+        };
+
+        let mut lines = Vec::new();
+
+        for item in element.child_elements() {
+            if item.value().name() != "li" {
+                self.unsupported(item.value().name());
+                continue;
+            }
+
+            let mut text = String::new();
+            let mut nested = Vec::new();
+            let mut blocks = Vec::new();
+
+            for node in item.children() {
+                if let Some(content) = node.value().as_text() {
+                    text.push_str(&self.text(content));
+                } else if let Some(child) = ElementRef::wrap(node) {
+                    match child.value().name() {
+                        "ul" | "ol" => nested.push(self.list(child, depth + 1)),
+                        "p" if blocks.is_empty() && tidy(&text).is_empty() => {
+                            text.push_str(&self.inline(child));
+                        }
+                        "pre" => blocks.push(pre(child)),
+                        "p" | "div" => blocks.push(tidy(&self.inline(child))),
+                        _ => text.push_str(&self.inline_element(child)),
+                    }
+                }
+            }
+
+            let mut entry = format!("{marker} {}", tidy(&text));
+            for block in blocks {
+                entry.push_str("\n+\n");
+                entry.push_str(&block);
+            }
+            for list in nested {
+                entry.push('\n');
+                entry.push_str(&list);
+            }
+            lines.push(entry);
+        }
+
+        lines.join("\n")
+    }
+
+    /// Convert a table. A first row of `<th>` cells or in `<thead>` becomes the header.
+    fn table(&mut self, element: ElementRef) -> String {
+        let mut text = String::new();
+
+        if let Some(caption) = find(element, "caption") {
+            text.push('.');
+            text.push_str(&tidy(&self.inline(caption)));
+            text.push('\n');
+        }
+        text.push_str("|===\n");
+
+        let rows: Vec<ElementRef> = element
+            .descendent_elements()
+            .filter(|row| row.value().name() == "tr")
+            .collect();
+
+        for (index, row) in rows.iter().enumerate() {
+            let cells: Vec<ElementRef> = row
+                .child_elements()
+                .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                .collect();
+            let is_header = index == 0
+                && (cells.iter().all(|cell| cell.value().name() == "th")
+                    || row
+                        .parent()
+                        .and_then(ElementRef::wrap)
+                        .is_some_and(|parent| parent.value().name() == "thead"));
+
+            let line = cells
+                .iter()
+                .map(|cell| format!("| {}", tidy(&self.inline(*cell)).replace('|', "\\|")))
+                .collect::<Vec<_>>()
+                .join(" ");
+            text.push_str(&line);
+            text.push('\n');
+            // A blank line after the first row marks it as the header.
+            if is_header {
+                text.push('\n');
+            }
+        }
+
+        text.push_str("|===");
+        text
+    }
+
+    /// Convert a definition list.
+    fn definition_list(&mut self, element: ElementRef) -> String {
+        element
+            .child_elements()
+            .map(|child| match child.value().name() {
+                "dt" => format!("{}::", tidy(&self.inline(child))),
+                "dd" => tidy(&self.inline(child)),
+                other => {
+                    self.unsupported(other);
+                    String::new()
+                }
+            })
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Convert the inline content of an element.
+    fn inline(&mut self, element: ElementRef) -> String {
+        let mut text = String::new();
+
+        for node in element.children() {
+            if let Some(content) = node.value().as_text() {
+                text.push_str(&self.text(content));
+            } else if let Some(child) = ElementRef::wrap(node) {
+                text.push_str(&self.inline_element(child));
+            }
+        }
+
+        text
+    }
+
+    /// Escape a text node. The module ID derives from the heading,
+    /// so a heading only escapes the attributes.
+    fn text(&self, text: &str) -> String {
+        if self.in_heading {
+            escape_attributes(text)
+        } else {
+            escape(text)
+        }
+    }
+
+    /// Convert a single inline element.
+    fn inline_element(&mut self, element: ElementRef) -> String {
+        let name = element.value().name();
+
+        match name {
+            "br" => return " +\n".to_string(),
+            "img" => return format!("image:{}", image(element)),
+            _ if SKIPPED.contains(&name) => {
+                self.unsupported(name);
+                return String::new();
+            }
+            _ => {}
+        }
+
+        let content = tidy(&self.inline(element));
+        let is_empty = content.is_empty();
+
+        let converted = match name {
+            "strong" | "b" => format!("*{content}*"),
+            "em" | "i" | "cite" => format!("_{content}_"),
+            "code" | "kbd" | "samp" | "tt" | "var" => {
+                format!("`+{}+`", tidy(&element.text().collect::<String>()))
+            }
+            "sup" => format!("^{content}^"),
+            "sub" => format!("~{content}~"),
+            "del" | "s" | "strike" => format!("[line-through]#{content}#"),
+            "a" => match element.attr("href") {
+                Some(href) => link(href, &content),
+                None => content,
+            },
+            "span" | "abbr" | "small" | "mark" | "u" | "font" | "label" | "time" => content,
+            // Block elements in inline content keep only their text.
+            _ if is_block(element) => format!(" {content} "),
+            _ => {
+                self.unsupported(name);
+                content
+            }
+        };
+
+        // Empty elements leave no formatting marks behind.
+        if is_empty {
+            String::new()
+        } else {
+            converted
+        }
+    }
+
+    /// Record an element that newdoc can't convert.
+    fn unsupported(&mut self, name: &str) {
+        *self.unsupported.entry(name.to_string()).or_default() += 1;
+    }
+
+    /// Warn about all the unsupported elements in the page.
+    fn report(&self) {
+        for message in self.report_messages() {
+            log::warn!("{message}");
+        }
+    }
+
+    /// Describe each unsupported element in the page and how often it occurs.
+    fn report_messages(&self) -> Vec<String> {
+        self.unsupported
+            .iter()
+            .map(|(name, count)| {
+                if SKIPPED.contains(&name.as_str()) {
+                    format!("Unsupported HTML element skipped: <{name}> ({count}×)")
+                } else {
+                    format!("Unsupported HTML element converted as plain text: <{name}> ({count}×)")
+                }
+            })
+            .collect()
+    }
+
+    /// Finish the conversion and return all the blocks.
+    fn finish(mut self) -> Vec<Block> {
+        if !self.current.trim().is_empty() {
+            self.blocks.push(Block::Content(self.current));
+        }
+        self.blocks
+    }
+}
+
+/// Check if the element starts a new block rather than continuing the inline content.
+fn is_block(element: ElementRef) -> bool {
+    matches!(
+        element.value().name(),
+        "h1" | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "p"
+            | "ul"
+            | "ol"
+            | "pre"
+            | "table"
+            | "blockquote"
+            | "hr"
+            | "dl"
+            | "div"
+            | "section"
+            | "article"
+            | "main"
+            | "header"
+            | "footer"
+            | "nav"
+            | "aside"
+            | "figure"
+            | "figcaption"
+            | "details"
+            | "summary"
+    ) || SKIPPED.contains(&element.value().name())
+}
+
+/// Convert a preformatted block, with the language from the `language-*` class, if any.
+fn pre(element: ElementRef) -> String {
+    let language = element
+        .descendent_elements()
+        .chain(std::iter::once(element))
+        .flat_map(|element| element.value().classes())
+        .find_map(|class| class.strip_prefix("language-"))
+        .map(|language| format!("[source,{language}]\n"))
+        .unwrap_or_default();
+    let text: String = element.text().collect();
+
+    format!("{language}----\n{}\n----", text.trim_matches('\n'))
+}
+
+/// The admonition label of a `<div>`, if one of its classes names an admonition.
+/// The class matches either the whole label, such as `warning`, or a label
+/// after a hyphen, such as `admonition-warning`.
+fn admonition(element: ElementRef) -> Option<String> {
+    element.value().classes().find_map(|class| {
+        let class = class.to_lowercase();
+        ADMONITIONS
+            .iter()
+            .find(|label| class == **label || class.ends_with(&format!("-{label}")))
+            .map(|label| label.to_uppercase())
+    })
+}
+
+/// The target and the alternative text of an image.
+fn image(element: ElementRef) -> String {
+    let source = element.attr("src").unwrap_or_default();
+    let alt = element.attr("alt").unwrap_or_default().replace(']', "\\]");
+    format!("{source}[{alt}]")
+}
+
+/// Find the first descendant element with this name.
+fn find<'a>(element: ElementRef<'a>, name: &str) -> Option<ElementRef<'a>> {
+    element
+        .descendent_elements()
+        .find(|element| element.value().name() == name)
+}
+
+/// Collapse the whitespace on each line, keeping the hard line breaks.
+fn tidy(text: &str) -> String {
+    text.split(" +\n")
+        .map(collapse)
+        .collect::<Vec<_>>()
+        .join(" +\n")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Widget knowledge base</title><script>track();</script></head>
+<body>
+<p>An introduction to <a href="https://example.com">widgets</a>.</p>
+<h2 id="about">About widgets</h2>
+<p>Widgets are <strong>useful</strong> for {product} users_.</p>
+<div class="confluence-information-macro-warning"><p>Handle with care.</p></div>
+<h2>Installing widgets</h2>
+<ol>
+  <li>Download the <code>widget_*</code>.</li>
+  <li>Install it:<pre><code class="language-bash">$ install widget</code></pre></li>
+</ol>
+<h2>Widget sizes</h2>
+<table>
+  <tr><th>Size</th><th>Width</th></tr>
+  <tr><td>Small</td><td>1 cm</td></tr>
+</table>
+<marquee>Old markup</marquee>
+</body>
+</html>
+"#;
+
+    #[test]
+    fn convert_html() {
+        let document = parse(PAGE).expect("Failed to convert valid HTML");
+
+        assert_eq!(document.title, "Widget knowledge base");
+        assert_eq!(
+            document.preamble,
+            "An introduction to https://example.com[widgets]."
+        );
+        assert_eq!(document.sections.len(), 3);
+
+        let about = &document.sections[0];
+        assert_eq!(about.id.as_deref(), Some("about"));
+        assert_eq!(
+            about.body.trim(),
+            "Widgets are *useful* for \\{product} users++_++.\n\n[WARNING]\n====\nHandle with care.\n===="
+        );
+
+        let installing = &document.sections[1];
+        assert_eq!(
+            installing.body.trim(),
+            ". Download the `+widget_*+`.\n. Install it:\n+\n[source,bash]\n----\n$ install widget\n----"
+        );
+
+        let sizes = &document.sections[2];
+        assert_eq!(
+            sizes.body.trim(),
+            "|===\n| Size | Width\n\n| Small | 1 cm\n|===\n\nOld markup"
+        );
+    }
+
+    #[test]
+    fn admonition_classes() {
+        let page = Html::parse_fragment(
+            r#"<div class="note"></div>
+<div class="admonition warning"></div>
+<div class="admonition-caution"></div>
+<div class="tooltip"></div>
+<div class="footnote"></div>"#,
+        );
+        let selector = scraper::Selector::parse("div").expect("Invalid selector");
+        let labels: Vec<Option<String>> = page.select(&selector).map(admonition).collect();
+
+        assert_eq!(
+            labels,
+            vec![
+                Some("NOTE".to_string()),
+                Some("WARNING".to_string()),
+                Some("CAUTION".to_string()),
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn report_unsupported_elements() {
+        let page = Html::parse_document(
+            "<body><p>Text <blink>a</blink> <marquee>b</marquee> <marquee>c</marquee>.</p>\
+            <script>track();</script><iframe src=\"ad.html\"></iframe></body>",
+        );
+        let mut converter = Converter::default();
+        converter.container(find(page.root_element(), "body").expect("The page has no body"));
+
+        assert_eq!(
+            converter.report_messages(),
+            vec![
+                "Unsupported HTML element converted as plain text: <blink> (1×)",
+                "Unsupported HTML element skipped: <iframe> (1×)",
+                "Unsupported HTML element converted as plain text: <marquee> (2×)",
+                "Unsupported HTML element skipped: <script> (1×)",
+            ]
+        );
+        assert_eq!(
+            converter.finish(),
+            vec![Block::Content("Text a b c.\n\n".to_string())]
+        );
+    }
+}
@@ -36,14 +36,27 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 use color_eyre::eyre::{bail, Result};
 
 pub mod cmd_line;
+mod collisions;
 pub mod config;
+mod convert;
+mod docbook;
+mod editor;
+mod existing;
+mod git;
+mod groups;
+mod html;
 pub mod logging;
+mod markdown;
 mod module;
+mod outline;
+mod split;
 mod templating;
+mod transaction;
 mod write;
 
-use cmd_line::{Cli, Verbosity};
+use cmd_line::{Action, Cli, Command, OutputFormat, Verbosity};
 pub use config::Options;
+use groups::Member;
 pub use module::{ContentType, Input, Module};
 
 /// newdoc uses many regular expressions at several places. Constructing them should never fail,
@@ -51,6 +64,12 @@ pub use module::{ContentType, Input, Module};
 /// error message through `expect`.
 const REGEX_ERROR: &str = "Failed to construct a regular expression. Please report this as a bug";
 
+/// Generate all the files that the user requested on the command line,
+/// or run the requested command.
+///
+/// # Errors
+///
+/// Fails if the command-line options are inconsistent or if newdoc cannot write a file.
 pub fn run(options: &Options, cli: &Cli) -> Result<()> {
     log::debug!("Active options:\n{:#?}", &options);
 
@@ -60,69 +79,118 @@ pub fn run(options: &Options, cli: &Cli) -> Result<()> {
                    Please switch to the Enki validation tool: <https://github.com/Levi-Leah/enki/>.");
     }
 
-    // Attach titles from the CLI to content types.
-    let content_types = [
-        (ContentType::Assembly, &cli.action.assembly),
-        (ContentType::Concept, &cli.action.concept),
-        (ContentType::Procedure, &cli.action.procedure),
-        (ContentType::Reference, &cli.action.reference),
-        (ContentType::Snippet, &cli.action.snippet),
-    ];
-
-    // Store all modules except for the populated assembly that will be created in this Vec
-    let mut non_populated: Vec<Module> = Vec::new();
-
-    // For each module type, see if it occurs on the command line and process it
-    for (content_type, titles) in content_types {
-        // Check if the given module type occurs on the command line
-        let mut modules = process_module_type(titles, content_type, options);
-
-        // Move all the newly created modules into the common Vec
-        non_populated.append(&mut modules);
+    if options.output == OutputFormat::Json {
+        if options.stdout {
+            bail!(
+                "The --stdout and --output json options both use the standard output. \
+                Use only one of them."
+            );
+        }
+        if options.show_text {
+            bail!(
+                "The --show-text and --output json options both use the standard output. \
+                Use only one of them."
+            );
+        }
     }
 
-    // Write all non-populated modules to the disk
-    for module in &non_populated {
-        module.write_file(options)?;
+    // Check that the target directories resolve before generating anything.
+    // The commands work with documents that can contain any content type.
+    if cli.command.is_some() {
+        for mod_type in ContentType::ALL {
+            options.target_dir_for(mod_type)?;
+        }
     }
 
-    // Treat the populated assembly module as a special case:
-    // * There can be only one populated assembly
-    // * It must be generated after the other modules so that it can use their include statements
-    if let Some(title) = &cli.action.include_in {
-        // Gather all include statements for the other modules
-        let include_statements: Vec<String> = non_populated
-            .into_iter()
-            .map(|module| module.include_statement)
-            .collect();
-
-        // The include_statements should never be empty thanks to the required group in clap.
+    let result = match &cli.command {
+        Some(command) if !cli.action.items.is_empty() => {
+            bail!(
+                "The `{}` command doesn't accept files to generate on the command line.",
+                command.name()
+            );
+        }
+        Some(Command::Plan { outline }) => outline::plan(outline, options),
+        Some(Command::Apply { outline }) => outline::apply(outline, options),
+        Some(Command::Split { interactive, file }) => split::split(file, options, *interactive),
+        Some(Command::Import { interactive, file }) => convert::import(file, options, *interactive),
+        None => generate(options, &cli.action),
+    };
+
+    if options.dry_run && result.is_ok() {
+        log::info!("Dry run: no files were written.");
+    }
+
+    result
+}
+
+/// Generate the modules and populated assemblies from the command-line items.
+fn generate(options: &Options, action: &Action) -> Result<()> {
+    // Sort the files from the command line into modules and populated assemblies.
+    let grouping = groups::assign(&action.items, &action.order)?;
+
+    // Check that the target directories resolve before generating anything.
+    let assemblies = grouping.groups.iter().map(|_| ContentType::Assembly);
+    for mod_type in grouping
+        .modules
+        .iter()
+        .map(|module| module.mod_type)
+        .chain(assemblies)
+    {
+        options.target_dir_for(mod_type)?;
+    }
+
+    // Check the new IDs and file names against the existing content in the repository.
+    let mut existing = collisions::Existing::scan(options);
+
+    // Generate all modules except for the populated assemblies
+    let non_populated: Vec<Module> = grouping
+        .modules
+        .iter()
+        .map(|module| {
+            let input = module
+                .overrides
+                .apply(Input::new(module.mod_type, &module.title, options));
+            existing.resolve(input, options).map(Module::from)
+        })
+        .collect::<Result<_>>()?;
+
+    // Treat the populated assemblies as a special case:
+    // They must be generated after the other modules so that they can use their include statements.
+    // Generate them before writing anything, so that an unreadable existing file
+    // stops newdoc before it writes any files.
+    let mut populated: Vec<Module> = Vec::new();
+
+    for group in &grouping.groups {
+        // Gather the include statements for the files in this group
+        let include_statements = group
+            .members
+            .iter()
+            .map(|member| match member {
+                Member::Module(position) => Ok(non_populated[*position].include_statement.clone()),
+                Member::Existing(path) => existing::include_statement_for(path),
+            })
+            .collect::<Result<Vec<String>>>()?;
+
+        // The include_statements should never be empty thanks to the group validation.
         // Make sure once more, though.
         if include_statements.is_empty() {
             bail!("The populated assembly includes no other files.");
         }
 
         // Generate the populated assembly module
-        let populated: Module = Input::new(ContentType::Assembly, title, options)
-            .include(include_statements)
-            .into();
+        let input = group
+            .overrides
+            .apply(Input::new(ContentType::Assembly, &group.title, options))
+            .include(include_statements);
+        let assembly: Module = existing.resolve(input, options)?.into();
 
-        populated.write_file(options)?;
+        populated.push(assembly);
     }
 
-    Ok(())
-}
-
-/// Process all titles that have been specified on the command line and that belong to a single
-/// module type.
-fn process_module_type(
-    titles: &[String],
-    content_type: ContentType,
-    options: &Options,
-) -> Vec<Module> {
-    let modules_from_type = titles
-        .iter()
-        .map(|title| Module::new(content_type, title, options));
+    // Write all non-populated modules to the disk, in the command-line order,
+    // followed by the populated assemblies
+    let all_modules: Vec<&Module> = non_populated.iter().chain(populated.iter()).collect();
+    let outcomes = write::write_modules(&all_modules, &[], options)?;
 
-    modules_from_type.collect()
+    write::check_skipped(&outcomes)
 }
@@ -23,7 +23,14 @@ use crate::Verbosity;
 
 /// This function initializes the `simplelog` logging system, which plugs into the `log`
 /// infrastructure. The function returns nothing. It only affects the global state when it runs.
-pub fn initialize_logger(verbosity: Verbosity) -> Result<()> {
+///
+/// If `stderr_only` is true, all messages go to stderr, so that stdout carries only
+/// the generated text.
+///
+/// # Errors
+///
+/// Fails if the logger cannot be initialized, such as when it's already been set.
+pub fn initialize_logger(verbosity: Verbosity, stderr_only: bool) -> Result<()> {
     // Set the verbosity level based on the command-line options.
     // Our `clap` configuration ensures that `verbose` and `quiet` can never be both true.
     let verbosity = match verbosity {
@@ -42,11 +49,17 @@ pub fn initialize_logger(verbosity: Verbosity) -> Result<()> {
         .set_thread_level(LevelFilter::Trace)
         .build();
 
+    let terminal_mode = if stderr_only {
+        TerminalMode::Stderr
+    } else {
+        // Mixed mode prints errors to stderr and info to stdout. Not sure about the other levels.
+        TerminalMode::Mixed
+    };
+
     TermLogger::init(
         verbosity,
         config,
-        // Mixed mode prints errors to stderr and info to stdout. Not sure about the other levels.
-        TerminalMode::Mixed,
+        terminal_mode,
         // Try to use color if possible.
         ColorChoice::Auto,
     )
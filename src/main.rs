@@ -27,8 +27,11 @@ fn main() -> Result<()> {
     // Parse the command-line options
     let cmdline_args = cmd_line::get_args();
 
-    // Initialize the logging system based on the set verbosity
-    logging::initialize_logger(cmdline_args.common_options.verbosity)?;
+    // Initialize the logging system based on the set verbosity.
+    // When newdoc prints the generated text, keep the log messages out of stdout.
+    let common = &cmdline_args.common_options;
+    let json = common.output == Some(cmd_line::OutputFormat::Json);
+    logging::initialize_logger(common.verbosity, common.stdout || common.show_text || json)?;
 
     // Set current options based on the command-line options and config files.
     let options = config::merge_configs(&cmdline_args)?;
@@ -0,0 +1,466 @@
+/*
+newdoc: Generate pre-populated documentation modules formatted with AsciiDoc.
+Copyright (C) 2026  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! # `markdown.rs`
+//!
+//! This module converts a Markdown document to AsciiDoc blocks,
+//! which the `convert` module then splits into modules.
+
+use color_eyre::eyre::Result;
+use pulldown_cmark::{
+    BlockQuoteKind, CodeBlockKind, Event, MetadataBlockKind, Options, Parser, Tag, TagEnd,
+};
+use regex::RegexBuilder;
+
+use crate::convert::{escape, escape_attributes, link, Block, Document};
+use crate::REGEX_ERROR;
+
+/// Parse the Markdown text and convert it to a document split into sections.
+pub fn parse(text: &str) -> Result<Document> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_HEADING_ATTRIBUTES
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+        | Options::ENABLE_GFM;
+
+    let mut converter = Converter::default();
+    for event in Parser::new_ext(text, options) {
+        converter.event(event);
+    }
+
+    let title = converter.front_matter_title.take();
+    Document::from_blocks(converter.finish(), title)
+}
+
+/// A list that the converter is currently inside of.
+#[derive(Debug)]
+struct List {
+    ordered: bool,
+    /// The number of blocks in the current item, which decides if the next block needs
+    /// the AsciiDoc list continuation
+    blocks: usize,
+}
+
+/// The state of the conversion from the stream of Markdown events to AsciiDoc.
+#[derive(Debug, Default)]
+struct Converter {
+    blocks: Vec<Block>,
+    /// The text being written. Inline elements such as links and table cells
+    /// collect their content in a separate buffer, which ends up in the parent.
+    buffers: Vec<String>,
+    lists: Vec<List>,
+    links: Vec<String>,
+    quotes: Vec<Option<BlockQuoteKind>>,
+    table_cells: Vec<String>,
+    heading: Option<(usize, Option<String>)>,
+    /// The delimiter that closes the current code block
+    code_delimiter: Option<&'static str>,
+    in_metadata: bool,
+    front_matter_title: Option<String>,
+}
+
+impl Converter {
+    /// The buffer that receives the text at the current position.
+    fn out(&mut self) -> &mut String {
+        if self.buffers.is_empty() {
+            self.buffers.push(String::new());
+        }
+        self.buffers
+            .last_mut()
+            .expect("The buffer stack is never empty here")
+    }
+
+    fn push(&mut self, text: &str) {
+        self.out().push_str(text);
+    }
+
+    /// Close the current buffer and return its content.
+    fn pop(&mut self) -> String {
+        self.buffers.pop().unwrap_or_default()
+    }
+
+    /// Make sure that the next text starts on a new line.
+    fn ensure_newline(&mut self) {
+        let out = self.out();
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+
+    /// Start a block that isn't the first in a list item, which needs a list continuation.
+    fn start_block(&mut self) {
+        match self.lists.last() {
+            // The first block of an item continues on the line of the item marker.
+            Some(list) if list.blocks == 0 => {}
+            Some(_) => {
+                self.ensure_newline();
+                self.push("+\n");
+            }
+            None => self.ensure_newline(),
+        }
+    }
+
+    /// Separate the block from the next one.
+    fn end_block(&mut self) {
+        self.ensure_newline();
+        if let Some(list) = self.lists.last_mut() {
+            list.blocks += 1;
+        } else {
+            self.push("\n");
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => {
+                if self.in_metadata {
+                    self.front_matter_title = front_matter_title(&text);
+                } else if self.code_delimiter.is_some() {
+                    self.push(&text);
+                } else if self.heading.is_some() {
+                    // The module ID derives from the title, so only the attributes are escaped.
+                    self.push(&escape_attributes(&text));
+                } else {
+                    self.push(&escape(&text));
+                }
+            }
+            Event::Code(code) => self.push(&format!("`+{code}+`")),
+            Event::Html(html) => {
+                log::warn!("Passing raw HTML through: {}", html.trim());
+                self.push(&html);
+            }
+            Event::InlineHtml(html) => {
+                log::warn!("Passing raw HTML through: {html}");
+                self.push(&format!("+++{html}+++"));
+            }
+            Event::SoftBreak => self.push("\n"),
+            Event::HardBreak => self.push(" +\n"),
+            Event::Rule => {
+                self.start_block();
+                self.push("'''\n");
+                self.end_block();
+            }
+            Event::TaskListMarker(checked) => self.push(if checked { "[x] " } else { "[ ] " }),
+            Event::FootnoteReference(name) | Event::InlineMath(name) | Event::DisplayMath(name) => {
+                log::warn!("Unsupported Markdown content left as plain text: {name}");
+                self.push(&name);
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.start_block(),
+            Tag::Heading { level, id, .. } => {
+                // Everything before the heading is finished content.
+                let content = self.pop();
+                if !content.trim().is_empty() {
+                    self.blocks.push(Block::Content(content));
+                }
+                self.heading = Some((level as usize, id.map(|id| id.to_string())));
+                self.buffers.push(String::new());
+            }
+            Tag::BlockQuote(kind) => {
+                self.start_block();
+                self.quotes.push(kind);
+                self.buffers.push(String::new());
+            }
+            Tag::CodeBlock(kind) => {
+                self.start_block();
+                let delimiter = match kind {
+                    CodeBlockKind::Fenced(language) if !language.is_empty() => {
+                        self.push(&format!("[source,{language}]\n"));
+                        "----\n"
+                    }
+                    CodeBlockKind::Fenced(_) => "----\n",
+                    CodeBlockKind::Indented => "....\n",
+                };
+                self.push(delimiter);
+                self.code_delimiter = Some(delimiter);
+            }
+            Tag::HtmlBlock => {
+                self.start_block();
+                self.push("++++\n");
+            }
+            Tag::List(first_number) => {
+                self.ensure_newline();
+                self.lists.push(List {
+                    ordered: first_number.is_some(),
+                    blocks: 0,
+                });
+            }
+            Tag::Item => {
+                self.ensure_newline();
+                let marker = match self.lists.last() {
+                    Some(list) if list.ordered => ".",
+                    _ => "*",
+                };
+                let depth = self.lists.len();
+                if let Some(list) = self.lists.last_mut() {
+                    list.blocks = 0;
+                }
+                self.push(&format!("{} ", marker.repeat(depth)));
+            }
+            Tag::Table(_) => {
+                self.start_block();
+                self.push("|===\n");
+            }
+            Tag::TableHead | Tag::TableRow => self.table_cells.clear(),
+            Tag::TableCell | Tag::Link { .. } | Tag::Image { .. } => {
+                if let Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } = tag {
+                    self.links.push(dest_url.to_string());
+                }
+                self.buffers.push(String::new());
+            }
+            Tag::Emphasis => self.push("_"),
+            Tag::Strong => self.push("*"),
+            Tag::Strikethrough => self.push("[line-through]#"),
+            Tag::Superscript => self.push("^"),
+            Tag::Subscript => self.push("~"),
+            Tag::MetadataBlock(MetadataBlockKind::YamlStyle) => self.in_metadata = true,
+            Tag::MetadataBlock(_)
+            | Tag::FootnoteDefinition(_)
+            | Tag::DefinitionList
+            | Tag::DefinitionListTitle
+            | Tag::DefinitionListDefinition => {
+                log::warn!("Unsupported Markdown element left as plain text: {tag:?}");
+            }
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Item => self.end_block(),
+            TagEnd::Heading(_) => {
+                let title = self.pop();
+                let (level, id) = self.heading.take().unwrap_or_default();
+                self.blocks.push(Block::Heading {
+                    level,
+                    title: title.trim().to_string(),
+                    id,
+                });
+            }
+            TagEnd::BlockQuote(_) => {
+                let content = self.pop();
+                let kind = self.quotes.pop().flatten();
+                self.push(&quote(content.trim(), kind));
+                self.end_block();
+            }
+            TagEnd::CodeBlock => {
+                self.ensure_newline();
+                let delimiter = self.code_delimiter.take().unwrap_or("----\n");
+                self.push(delimiter);
+                self.end_block();
+            }
+            TagEnd::HtmlBlock => {
+                self.ensure_newline();
+                self.push("++++\n");
+                self.end_block();
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+                // A nested list counts as a block of the parent item.
+                if let Some(list) = self.lists.last_mut() {
+                    list.blocks += 1;
+                } else {
+                    self.ensure_newline();
+                    self.push("\n");
+                }
+            }
+            TagEnd::Table => {
+                self.push("|===\n");
+                self.end_block();
+            }
+            TagEnd::TableHead => {
+                // A blank line after the first row marks it as the header.
+                let row = table_row(&self.table_cells);
+                self.push(&format!("{row}\n\n"));
+            }
+            TagEnd::TableRow => {
+                let row = table_row(&self.table_cells);
+                self.push(&format!("{row}\n"));
+            }
+            TagEnd::TableCell => {
+                let cell = self.pop();
+                self.table_cells.push(cell);
+            }
+            TagEnd::Link => {
+                let text = self.pop();
+                let target = self.links.pop().unwrap_or_default();
+                self.push(&link(&target, &text));
+            }
+            TagEnd::Image => {
+                let alt = self.pop();
+                let target = self.links.pop().unwrap_or_default();
+                self.push(&format!("image:{target}[{}]", alt.replace(']', "\\]")));
+            }
+            TagEnd::Emphasis => self.push("_"),
+            TagEnd::Strong => self.push("*"),
+            TagEnd::Strikethrough => self.push("#"),
+            TagEnd::Superscript => self.push("^"),
+            TagEnd::Subscript => self.push("~"),
+            TagEnd::MetadataBlock(_) => self.in_metadata = false,
+            TagEnd::FootnoteDefinition
+            | TagEnd::DefinitionList
+            | TagEnd::DefinitionListTitle
+            | TagEnd::DefinitionListDefinition => {}
+        }
+    }
+
+    /// Finish the conversion and return all the blocks.
+    fn finish(mut self) -> Vec<Block> {
+        let content = self.pop();
+        if !content.trim().is_empty() {
+            self.blocks.push(Block::Content(content));
+        }
+        self.blocks
+    }
+}
+
+/// Convert the content of a block quote to an admonition or to an AsciiDoc quote.
+/// Recognizes the GitHub alert syntax, such as `> [!NOTE]`,
+/// and quotes that start with a bold label, such as `> **Note:**`.
+fn quote(content: &str, kind: Option<BlockQuoteKind>) -> String {
+    let label = match kind {
+        Some(BlockQuoteKind::Note) => Some("NOTE".to_string()),
+        Some(BlockQuoteKind::Tip) => Some("TIP".to_string()),
+        Some(BlockQuoteKind::Important) => Some("IMPORTANT".to_string()),
+        Some(BlockQuoteKind::Warning) => Some("WARNING".to_string()),
+        Some(BlockQuoteKind::Caution) => Some("CAUTION".to_string()),
+        None => None,
+    };
+
+    if let Some(label) = label {
+        return format!("[{label}]\n====\n{content}\n====\n");
+    }
+
+    let bold_label = RegexBuilder::new(r"^\*(note|tip|important|warning|caution)(:\*|\*:?)\s*")
+        .case_insensitive(true)
+        .build()
+        .expect(REGEX_ERROR);
+
+    if let Some(captures) = bold_label.captures(content) {
+        let label = captures[1].to_uppercase();
+        let rest = &content[captures[0].len()..];
+        format!("[{label}]\n====\n{rest}\n====\n")
+    } else {
+        format!("____\n{content}\n____\n")
+    }
+}
+
+/// Format the cells of a table row, escaping the cell separator in the cell content.
+fn table_row(cells: &[String]) -> String {
+    cells
+        .iter()
+        .map(|cell| format!("| {}", cell.trim().replace('|', "\\|")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Find the title in the YAML front matter, if it sets one.
+fn front_matter_title(front_matter: &str) -> Option<String> {
+    front_matter.lines().find_map(|line| {
+        let title = line.strip_prefix("title:")?.trim();
+        let title = title.trim_matches(['"', '\'']);
+        (!title.is_empty()).then(|| title.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const README: &str = r"# My project
+
+An introduction with a [link](https://example.com).
+
+## Installing {#installing}
+
+1. Download {product} from *the* site, or 2 * 3 mirrors.
+2. Run the installer:
+
+   ```sh
+   $ ./install
+   ```
+
+> [!NOTE]
+> Installation requires root.
+
+## Options
+
+| Option | Meaning |
+|--------|---------|
+| `-v`   | Verbose |
+
+### Details
+
+> A quote.
+";
+
+    #[test]
+    fn convert_markdown() {
+        let document = parse(README).expect("Failed to convert valid Markdown");
+
+        assert_eq!(document.title, "My project");
+        assert_eq!(
+            document.preamble,
+            "An introduction with a https://example.com[link]."
+        );
+        assert_eq!(document.sections.len(), 2);
+
+        let installing = &document.sections[0];
+        assert_eq!(installing.id.as_deref(), Some("installing"));
+        assert_eq!(
+            installing.body.trim(),
+            ". Download \\{product} from _the_ site, or 2 {asterisk} 3 mirrors.\n\
+            . Run the installer:\n\
+            +\n\
+            [source,sh]\n----\n$ ./install\n----\n\n\
+            [NOTE]\n====\nInstallation requires root.\n===="
+        );
+
+        let options = &document.sections[1];
+        assert_eq!(
+            options.body.trim(),
+            "|===\n| Option | Meaning\n\n| `+-v+` | Verbose\n|===\n\n\
+            == Details\n\n\
+            ____\nA quote.\n____"
+        );
+    }
+
+    #[test]
+    fn admonition_labels() {
+        assert_eq!(
+            quote("*Warning:* Be careful.", None),
+            "[WARNING]\n====\nBe careful.\n====\n"
+        );
+        assert_eq!(quote("Plain.", None), "____\nPlain.\n____\n");
+    }
+
+    #[test]
+    fn front_matter() {
+        let document = parse("---\ntitle: \"From front matter\"\n---\n\n## A section\n\nText.\n")
+            .expect("Failed to convert valid Markdown");
+
+        assert_eq!(document.title, "From front matter");
+        assert_eq!(document.sections[0].title, "A section");
+    }
+}
@@ -20,11 +20,71 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use std::fmt;
 use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
 
+use color_eyre::eyre::{bail, Report, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::cmd_line::{IdFallback, IdStyle};
 use crate::Options;
 
+/// Semantic markup roles that newdoc removes from the ID, such as `[command]`.
+/// The `semantic_roles` option adds more roles.
+const SEMANTIC_ROLES: [&str; 12] = [
+    "package",
+    "option",
+    "parameter",
+    "variable",
+    "command",
+    "replaceable",
+    "filename",
+    "literal",
+    "systemitem",
+    "application",
+    "function",
+    "gui",
+];
+
+/// Characters that aren't allowed in the ID, and their replacements,
+/// usually a dash or an empty string.
+/// The `id_substitutions` option adds rules that apply before these.
+const SUBSTITUTIONS: [(&str, &str); 27] = [
+    (" ", "-"),
+    ("(", ""),
+    (")", ""),
+    ("?", ""),
+    ("!", ""),
+    ("'", ""),
+    ("\"", ""),
+    ("#", ""),
+    ("%", ""),
+    ("&", ""),
+    ("*", ""),
+    (",", "-"),
+    (".", "-"),
+    ("/", "-"),
+    (":", "-"),
+    (";", ""),
+    ("@", "-at-"),
+    ("\\", ""),
+    ("`", ""),
+    ("$", ""),
+    ("^", ""),
+    ("|", ""),
+    ("=", "-"),
+    // Remove square brackets only after semantic markup:
+    ("[", ""),
+    ("]", ""),
+    // TODO: Curly braces shouldn't appear in the title in the first place.
+    // They'd be interpreted as attributes there.
+    // Print an error in that case? Escape them with AsciiDoc escapes?
+    ("{", ""),
+    ("}", ""),
+];
+
 /// All possible types of the AsciiDoc module
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ContentType {
     Assembly,
     Concept,
@@ -47,6 +107,58 @@ impl fmt::Display for ContentType {
     }
 }
 
+// Parse the content type from its name, such as in the `:_mod-docs-content-type:` attribute.
+// The name is case-insensitive.
+impl FromStr for ContentType {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mod_type = match s.to_lowercase().as_str() {
+            "assembly" => Self::Assembly,
+            "concept" => Self::Concept,
+            "procedure" => Self::Procedure,
+            "reference" => Self::Reference,
+            "snippet" => Self::Snippet,
+            _ => bail!("Unknown content type: `{s}`."),
+        };
+
+        Ok(mod_type)
+    }
+}
+
+impl ContentType {
+    /// All the content types.
+    pub const ALL: [ContentType; 5] = [
+        Self::Assembly,
+        Self::Concept,
+        Self::Procedure,
+        Self::Reference,
+        Self::Snippet,
+    ];
+
+    /// Pick the right file and ID prefix depending on the content type.
+    #[must_use]
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Assembly => "assembly_",
+            Self::Concept => "con_",
+            Self::Procedure => "proc_",
+            Self::Reference => "ref_",
+            Self::Snippet => "snip_",
+        }
+    }
+
+    /// The first directory in the include path is either `assemblies/` or `modules/`,
+    /// based on the module type, or `snippets/` for snippet files.
+    fn include_root(self) -> &'static str {
+        match self {
+            Self::Assembly => "assemblies",
+            Self::Snippet => "snippets",
+            _ => "modules",
+        }
+    }
+}
+
 /// An initial representation of the module with input data, used to construct the `Module` struct
 #[derive(Debug)]
 pub struct Input {
@@ -54,6 +166,14 @@ pub struct Input {
     pub title: String,
     pub options: Options,
     pub includes: Option<Vec<String>>,
+    /// An ID that replaces the ID derived from the title, such as an ID kept from converted content
+    pub explicit_id: Option<String>,
+    /// A file name that replaces the file name derived from the ID
+    pub explicit_file_name: Option<String>,
+    /// A number that newdoc appends to the derived ID to make it unique in the repository
+    pub id_suffix: Option<usize>,
+    /// Content that replaces the template placeholders, such as a converted section
+    pub body: Option<String>,
 }
 
 /// A representation of the module with all its metadata and the generated AsciiDoc content
@@ -63,25 +183,38 @@ pub struct Module {
     title: String,
     anchor: String,
     pub file_name: String,
+    target_dir: PathBuf,
     pub include_statement: String,
     includes: Option<Vec<String>>,
     pub text: String,
+    /// If the final ID is degenerate, the advice how to fix it
+    degenerate_id: Option<&'static str>,
 }
 
 /// Construct a basic builder for `Module`, storing information from the user input.
 impl Input {
     #[must_use]
     pub fn new(mod_type: ContentType, title: &str, options: &Options) -> Input {
-        log::debug!("Processing title `{}` of type `{:?}`", title, mod_type);
+        log::debug!("Processing title `{title}` of type `{mod_type:?}`");
 
         let title = String::from(title);
-        let options = options.clone();
+        let mut options = options.clone();
+
+        // Each content type can have its own target directory.
+        // The `run` function checks in advance that the directory resolves.
+        if let Ok(target_dir) = options.target_dir_for(mod_type) {
+            options.target_dir = target_dir;
+        }
 
         Input {
             mod_type,
             title,
             options,
             includes: None,
+            explicit_id: None,
+            explicit_file_name: None,
+            id_suffix: None,
+            body: None,
         }
     }
 
@@ -92,6 +225,30 @@ impl Input {
         self
     }
 
+    /// Use this ID instead of the ID derived from the title.
+    /// The anchor uses the ID verbatim, without a prefix, so that existing links keep working.
+    #[must_use]
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.explicit_id = Some(id.to_string());
+        self
+    }
+
+    /// Use this file name instead of the file name derived from the ID.
+    /// newdoc adds the `.adoc` extension if the file name doesn't have it.
+    #[must_use]
+    pub fn with_file_name(mut self, file_name: &str) -> Self {
+        self.explicit_file_name = Some(file_name.to_string());
+        self
+    }
+
+    /// Use this content as the body of the module instead of the template placeholders.
+    /// The generated file then only adds the newdoc header, the ID, and the title.
+    #[must_use]
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = Some(body.to_string());
+        self
+    }
+
     /// Create an ID string that is derived from the human-readable title. The ID is usable as:
     ///
     /// * An AsciiDoc section ID
@@ -110,62 +267,157 @@ impl Input {
     ///
     /// assert_eq!("a-test-with-problematic-characters", input.id());
     /// ```
+    ///
+    /// By default, each non-ASCII character becomes a dash. The transliteration style
+    /// converts the characters to their ASCII equivalents first:
+    ///
+    /// ```
+    /// use newdoc::{cmd_line::IdStyle, ContentType, Input, Options};
+    ///
+    /// let title = "Konfigurace síťového rozhraní";
+    /// let options = Options::default();
+    /// let input = Input::new(ContentType::Procedure, title, &options);
+    ///
+    /// assert_eq!("konfigurace-s-ov-ho-rozhran", input.id());
+    ///
+    /// let options = Options {
+    ///     id_style: IdStyle::Transliterate,
+    ///     ..Default::default()
+    /// };
+    /// let input = Input::new(ContentType::Procedure, title, &options);
+    ///
+    /// assert_eq!("konfigurace-sitoveho-rozhrani", input.id());
+    /// ```
+    ///
+    /// The configured substitutions apply first, then newdoc removes the semantic markup,
+    /// and then the built-in substitutions apply:
+    ///
+    /// ```
+    /// use newdoc::{ContentType, Input, Options};
+    ///
+    /// let options = Options::default();
+    /// let input = Input::new(ContentType::Concept, "Rock & roll with [gui]`C++`", &options);
+    /// assert_eq!("rock-roll-with-c", input.id());
+    ///
+    /// let options = Options {
+    ///     id_substitutions: vec![
+    ///         ("&".to_string(), "and".to_string()),
+    ///         ("+".to_string(), "plus".to_string()),
+    ///     ],
+    ///     ..Default::default()
+    /// };
+    /// let input = Input::new(ContentType::Concept, "Rock & roll with [gui]`C++`", &options);
+    /// assert_eq!("rock-and-roll-with-cplusplus", input.id());
+    ///
+    /// // Without the role in the list, only the brackets disappear.
+    /// let title = "Clicking [guilabel]`Save`";
+    /// let input = Input::new(ContentType::Procedure, title, &options);
+    /// assert_eq!("clicking-guilabelsave", input.id());
+    ///
+    /// let options = Options {
+    ///     semantic_roles: vec!["guilabel".to_string()],
+    ///     ..Default::default()
+    /// };
+    /// let input = Input::new(ContentType::Procedure, title, &options);
+    /// assert_eq!("clicking-save", input.id());
+    /// ```
     #[must_use]
     pub fn id(&self) -> String {
-        let title = &self.title;
+        if let Some(id) = &self.explicit_id {
+            return id.clone();
+        }
+
+        let suffix = self
+            .id_suffix
+            .map(|suffix| format!("-{suffix}"))
+            .unwrap_or_default();
+        let id = self.full_id();
+
+        // Shorten the ID so that it fits in the maximum length, including the suffix
+        // that makes the ID unique if it collides with existing content.
+        let id = match self.options.max_id_length {
+            Some(max_length) => truncate_id(&id, max_length.saturating_sub(suffix.len())),
+            None => id,
+        };
+
+        id + &suffix
+    }
+
+    /// The ID derived from the title, or from the fallback scheme, before any shortening.
+    fn full_id(&self) -> String {
+        self.fallback_id()
+            .unwrap_or_else(|| self.derived_id(self.options.id_style))
+    }
+
+    /// If the title results in a degenerate ID, such as an empty ID from a title in Chinese,
+    /// return the ID from the fallback scheme that the `id_fallback` option sets.
+    /// Returns `None` if the ID is fine, or if the fallback is `error`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use newdoc::{cmd_line::IdFallback, ContentType, Input, Options};
+    ///
+    /// let title = "安装产品";
+    /// let options = Options::default();
+    /// let input = Input::new(ContentType::Procedure, title, &options);
+    /// assert_eq!("", input.id());
+    /// assert_eq!(None, input.fallback_id());
+    ///
+    /// let options = Options {
+    ///     id_fallback: IdFallback::Transliterate,
+    ///     ..Default::default()
+    /// };
+    /// let input = Input::new(ContentType::Procedure, title, &options);
+    /// assert_eq!("an-zhuang-chan-pin", input.id());
+    ///
+    /// let options = Options {
+    ///     id_fallback: IdFallback::Hash,
+    ///     ..Default::default()
+    /// };
+    /// let input = Input::new(ContentType::Procedure, title, &options);
+    /// assert!(input.id().starts_with("id-"));
+    /// assert_eq!("proc_".len() + 11 + ".adoc".len(), input.file_name().len());
+    /// ```
+    #[must_use]
+    pub fn fallback_id(&self) -> Option<String> {
+        let id = self.derived_id(self.options.id_style);
+
+        if !is_degenerate(&id, self.options.min_id_length) {
+            return None;
+        }
+
+        match self.options.id_fallback {
+            IdFallback::Error => None,
+            IdFallback::Transliterate => Some(self.derived_id(IdStyle::Transliterate)),
+            IdFallback::Hash => Some(format!("id-{:08x}", fnv1a(&self.title))),
+        }
+    }
+
+    /// Derive the ID from the title, converting non-ASCII characters in this style.
+    fn derived_id(&self, id_style: IdStyle) -> String {
+        // Convert the non-ASCII characters to ASCII if enabled, such as `ß` to `ss`.
+        // The default style keeps the IDs compatible with the previous releases.
+        let title = match id_style {
+            IdStyle::Legacy => self.title.clone(),
+            IdStyle::Transliterate => deunicode::deunicode(&self.title),
+        };
         // The ID is all lower-case
-        let mut title_with_replacements: String = String::from(title).to_lowercase();
-
-        // Replace characters that aren't allowed in the ID, usually with a dash or an empty string
-        let substitutions = [
-            (" ", "-"),
-            ("(", ""),
-            (")", ""),
-            ("?", ""),
-            ("!", ""),
-            ("'", ""),
-            ("\"", ""),
-            ("#", ""),
-            ("%", ""),
-            ("&", ""),
-            ("*", ""),
-            (",", "-"),
-            (".", "-"),
-            ("/", "-"),
-            (":", "-"),
-            (";", ""),
-            ("@", "-at-"),
-            ("\\", ""),
-            ("`", ""),
-            ("$", ""),
-            ("^", ""),
-            ("|", ""),
-            ("=", "-"),
-            // Remove known semantic markup from the ID:
-            ("[package]", ""),
-            ("[option]", ""),
-            ("[parameter]", ""),
-            ("[variable]", ""),
-            ("[command]", ""),
-            ("[replaceable]", ""),
-            ("[filename]", ""),
-            ("[literal]", ""),
-            ("[systemitem]", ""),
-            ("[application]", ""),
-            ("[function]", ""),
-            ("[gui]", ""),
-            // Remove square brackets only after semantic markup:
-            ("[", ""),
-            ("]", ""),
-            // TODO: Curly braces shouldn't appear in the title in the first place.
-            // They'd be interpreted as attributes there.
-            // Print an error in that case? Escape them with AsciiDoc escapes?
-            ("{", ""),
-            ("}", ""),
-        ];
-
-        // Perform all the defined replacements on the title
-        for (old, new) in substitutions {
+        let mut title_with_replacements: String = title.to_lowercase();
+
+        // Apply the substitution rules in a fixed order:
+        //
+        // 1. The configured substitutions, so that they can override the built-in ones
+        // 2. The removal of semantic markup, both built-in and configured
+        // 3. The built-in substitutions, which remove the remaining square brackets
+        for (old, new) in &self.options.id_substitutions {
+            title_with_replacements = title_with_replacements.replace(old.as_str(), new);
+        }
+        let configured_roles = self.options.semantic_roles.iter().map(String::as_str);
+        for role in SEMANTIC_ROLES.into_iter().chain(configured_roles) {
+            title_with_replacements = title_with_replacements.replace(&format!("[{role}]"), "");
+        }
+        for (old, new) in SUBSTITUTIONS {
             title_with_replacements = title_with_replacements.replace(old, new);
         }
 
@@ -220,13 +472,30 @@ impl Input {
     /// ```
     #[must_use]
     pub fn file_name(&self) -> String {
+        if let Some(file_name) = &self.explicit_file_name {
+            let has_extension = Path::new(file_name)
+                .extension()
+                .is_some_and(|extension| extension == "adoc");
+            return if has_extension {
+                file_name.clone()
+            } else {
+                format!("{file_name}.adoc")
+            };
+        }
+
         // Add a prefix only if they're enabled.
         let prefix = if self.options.file_prefixes {
-            self.prefix()
+            self.mod_type.prefix()
         } else {
             ""
         };
 
+        // An explicit ID might already start with the prefix. Don't repeat it.
+        let prefix = match &self.explicit_id {
+            Some(id) if id.starts_with(prefix) => "",
+            _ => prefix,
+        };
+
         let id = self.id();
 
         let suffix = ".adoc";
@@ -261,9 +530,14 @@ impl Input {
     /// assert_eq!("con_prefix-anchor-configuration", input.anchor());
     #[must_use]
     pub fn anchor(&self) -> String {
+        // An explicit ID is the complete anchor.
+        if let Some(id) = &self.explicit_id {
+            return id.clone();
+        }
+
         // Add a prefix only if they're enabled.
         let prefix = if self.options.anchor_prefixes {
-            self.prefix()
+            self.mod_type.prefix()
         } else {
             ""
         };
@@ -273,88 +547,195 @@ impl Input {
         [prefix, &id].join("")
     }
 
-    /// Pick the right file and ID prefix depending on the content type.
-    fn prefix(&self) -> &'static str {
-        match self.mod_type {
-            ContentType::Assembly => "assembly_",
-            ContentType::Concept => "con_",
-            ContentType::Procedure => "proc_",
-            ContentType::Reference => "ref_",
-            ContentType::Snippet => "snip_",
-        }
-    }
-
     /// Prepare an include statement that can be used to include the generated file from elsewhere.
     fn include_statement(&self) -> String {
-        let path_placeholder = Path::new("<path>").to_path_buf();
+        include_statement(self.mod_type, &self.options.target_dir, &self.file_name())
+    }
+}
 
-        let include_path = match self.infer_include_dir() {
-            Some(path) => path,
-            None => path_placeholder,
-        };
+/// Shorten the ID to the maximum length. The ID keeps as many whole words as fit,
+/// or it's cut in the middle of the first word if even that doesn't fit.
+///
+/// # Examples
+///
+/// ```
+/// use newdoc::{ContentType, Input, Options};
+///
+/// let title = "Configuring the network interfaces on the installed system";
+/// let options = Options {
+///     max_id_length: Some(30),
+///     ..Default::default()
+/// };
+/// let input = Input::new(ContentType::Procedure, title, &options);
+///
+/// assert_eq!("configuring-the-network", input.id());
+/// assert_eq!("proc_configuring-the-network.adoc", input.file_name());
+/// ```
+fn truncate_id(id: &str, max_length: usize) -> String {
+    if id.len() <= max_length {
+        return id.to_string();
+    }
 
-        format!(
-            "include::{}/{}[leveloffset=+1]",
-            include_path.display(),
-            &self.file_name()
-        )
+    // The ID is ASCII at this point, so byte positions are character positions.
+    match id[..=max_length].rfind('-') {
+        Some(boundary) if boundary > 0 => id[..boundary].to_string(),
+        _ => id[..max_length].trim_end_matches('-').to_string(),
     }
+}
 
-    /// Determine the start of the include statement from the target path.
-    /// Returns the relative path that can be used in the include statement, if it's possible
-    /// to determine it automatically.
-    fn infer_include_dir(&self) -> Option<PathBuf> {
-        // The first directory in the include path is either `assemblies/` or `modules/`,
-        // based on the module type, or `snippets/` for snippet files.
-        let include_root = match &self.mod_type {
-            ContentType::Assembly => "assemblies",
-            ContentType::Snippet => "snippets",
-            _ => "modules",
-        };
+/// Check if the ID has fewer ASCII letters and digits than the minimum length,
+/// such as an empty ID from a title without Latin characters.
+#[must_use]
+pub fn is_degenerate(id: &str, min_length: usize) -> bool {
+    id.chars().filter(char::is_ascii_alphanumeric).count() < min_length
+}
 
-        // TODO: Maybe convert the path earlier in the module building.
-        let relative_path = Path::new(&self.options.target_dir);
-        // Try to find the root element in an absolute path.
-        // If the absolute path cannot be constructed due to an error, search the relative path instead.
-        let target_path = match relative_path.canonicalize() {
-            Ok(path) => path,
-            Err(_) => relative_path.to_path_buf(),
-        };
+/// Check that an explicit ID only contains ASCII letters, digits, dashes, underscores,
+/// and periods, so that it's safe to use in the anchor and in the file name.
+#[must_use]
+pub fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+}
 
-        // Split the target path into components
-        let component_vec: Vec<_> = target_path
-            .as_path()
-            .components()
-            .map(Component::as_os_str)
-            .collect();
+/// A 32-bit FNV-1a hash of the text. Unlike the standard library hasher,
+/// the result is stable across releases, so the same title always results in the same ID.
+fn fnv1a(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
 
-        // Find the position of the component that matches the root element,
-        // searching from the end of the path forward.
-        let root_position = component_vec.iter().rposition(|&c| c == include_root);
+/// Prepare an include statement for a file of this content type, stored in this directory.
+pub fn include_statement(mod_type: ContentType, target_dir: &Path, file_name: &str) -> String {
+    let path_placeholder = Path::new("<path>").to_path_buf();
 
-        // If there is such a root element in the path, construct the include path.
-        // TODO: To be safe, check that the root path element still exists in a Git repository.
-        if let Some(position) = root_position {
-            let include_path = component_vec[position..].iter().collect::<PathBuf>();
-            Some(include_path)
-        // If no appropriate root element was found, use a generic placeholder.
-        } else {
-            None
+    let include_path = match infer_include_dir(mod_type, target_dir) {
+        Some(path) => path,
+        None => path_placeholder,
+    };
+
+    format!(
+        "include::{}/{}[leveloffset=+1]",
+        include_path.display(),
+        file_name
+    )
+}
+
+/// Determine the start of the include statement from the target path.
+/// Returns the relative path that can be used in the include statement, if it's possible
+/// to determine it automatically.
+fn infer_include_dir(mod_type: ContentType, target_dir: &Path) -> Option<PathBuf> {
+    let include_root = mod_type.include_root();
+
+    // Try to find the root element in an absolute path, even if the directory doesn't exist yet.
+    // If the absolute path cannot be constructed due to an error, search the relative path instead.
+    let target_path = match absolute_path(target_dir) {
+        Some(path) => path,
+        None => target_dir.to_path_buf(),
+    };
+
+    // Split the target path into components
+    let component_vec: Vec<_> = target_path
+        .as_path()
+        .components()
+        .map(Component::as_os_str)
+        .collect();
+
+    // Find the position of the component that matches the root element,
+    // searching from the end of the path forward.
+    let root_position = component_vec.iter().rposition(|&c| c == include_root);
+
+    // If there is such a root element in the path, construct the include path.
+    // TODO: To be safe, check that the root path element still exists in a Git repository.
+    if let Some(position) = root_position {
+        let include_path = component_vec[position..].iter().collect::<PathBuf>();
+        Some(include_path)
+    // If no appropriate root element was found, use a generic placeholder.
+    } else {
+        None
+    }
+}
+
+/// Construct the absolute path to a file or directory that might not exist yet.
+/// The longest existing part of the path is canonicalized, and the missing
+/// components are appended to it. Returns `None` if no part of the path exists
+/// or if the missing part cannot be appended, such as when it ends with `..`.
+#[must_use]
+pub fn absolute_path(path: &Path) -> Option<PathBuf> {
+    let mut missing = Vec::new();
+    let mut existing = path;
+
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            let absolute = missing
+                .iter()
+                .rev()
+                .fold(canonical, |path, component| path.join(component));
+            return Some(absolute);
         }
+
+        missing.push(existing.file_name()?);
+        existing = match existing.parent()? {
+            // The parent of a relative path with one component is empty.
+            parent if parent.as_os_str().is_empty() => Path::new("."),
+            parent => parent,
+        };
     }
 }
 
 impl From<Input> for Module {
     /// Convert the `Input` builder struct into the finished `Module` struct.
     fn from(input: Input) -> Self {
+        let fallback_id = input.fallback_id();
+
+        if let Some(fallback_id) = &fallback_id {
+            log::warn!(
+                "The title `{}` results in the degenerate `{}` ID. Using `{}` instead.",
+                input.title,
+                input.derived_id(input.options.id_style),
+                fallback_id
+            );
+        }
+
+        if let Some(max_length) = input.options.max_id_length {
+            let full_id = input.full_id();
+            if input.explicit_id.is_none() && full_id.len() > max_length {
+                log::info!(
+                    "‣ ID shortened to {max_length} characters: {full_id} → {}",
+                    input.id()
+                );
+            }
+        }
+
+        // Check the final ID, which might have a suffix or be shortened, even from a fallback.
+        let degenerate_id = (input.explicit_id.is_none()
+            && is_degenerate(&input.id(), input.options.min_id_length))
+        .then(|| {
+            if !input.id().starts_with(&input.full_id()) {
+                "Increase the maximum ID length with the `--max-id-length` option."
+            } else if input.body.is_some() {
+                // Converted sections don't accept the `--id` option.
+                "Rename the section in the source document, or set a fallback \
+                with the `--id-fallback transliterate` or `--id-fallback hash` option."
+            } else {
+                "Use a title with Latin characters, set the ID with the `--id` option, \
+                or set a fallback with the `--id-fallback transliterate` or `--id-fallback hash` option."
+            }
+        });
+
         let module = Module {
             mod_type: input.mod_type,
             title: input.title.clone(),
             anchor: input.anchor(),
             file_name: input.file_name(),
+            target_dir: input.options.target_dir.clone(),
             include_statement: input.include_statement(),
             includes: input.includes.clone(),
             text: input.text(),
+            degenerate_id,
         };
 
         log::debug!("Generated module properties:");
@@ -383,6 +764,58 @@ impl Module {
         let input = Input::new(mod_type, title, options);
         input.into()
     }
+
+    /// The content type of the module.
+    #[must_use]
+    pub fn mod_type(&self) -> ContentType {
+        self.mod_type
+    }
+
+    /// The title of the module.
+    #[must_use]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// The directory where newdoc saves the module.
+    #[must_use]
+    pub fn target_dir(&self) -> &Path {
+        &self.target_dir
+    }
+
+    /// The path where newdoc saves the module: the target directory joined with the file name.
+    #[must_use]
+    pub fn full_path(&self) -> PathBuf {
+        self.target_dir.join(&self.file_name)
+    }
+
+    /// The ID (anchor) of the module, without the `_{context}` suffix.
+    #[must_use]
+    pub fn anchor(&self) -> &str {
+        &self.anchor
+    }
+
+    /// Check that the title resulted in a usable ID.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the final ID is empty or shorter than the `min_id_length` option,
+    /// either because the `id_fallback` option didn't replace it,
+    /// or because the `max_id_length` option shortened it.
+    pub fn check_id(&self) -> Result<()> {
+        if let Some(hint) = self.degenerate_id {
+            bail!(
+                "The {} `{}` results in the `{}` file with the `{}` ID, \
+                which has too few ASCII letters or digits.\n{hint}",
+                self.mod_type,
+                self.title,
+                self.file_name,
+                self.anchor
+            );
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -472,4 +905,97 @@ mod tests {
             "include::modules/topic/proc_testing-the-detected-path.adoc[leveloffset=+1]"
         );
     }
+
+    #[test]
+    fn absolute_path_of_missing_directory() {
+        let current = std::env::current_dir().expect("Failed to read the current directory");
+        let current = current
+            .canonicalize()
+            .expect("Failed to canonicalize a path");
+
+        assert_eq!(
+            absolute_path(Path::new("missing/modules/topic")),
+            Some(current.join("missing/modules/topic"))
+        );
+        assert_eq!(absolute_path(Path::new(".")), Some(current));
+    }
+
+    #[test]
+    fn transliterated_ids() {
+        let options = Options {
+            id_style: IdStyle::Transliterate,
+            ..basic_options()
+        };
+
+        let input = Input::new(ContentType::Concept, "Straße und Øl", &options);
+        assert_eq!(input.id(), "strasse-und-ol");
+
+        let input = Input::new(ContentType::Concept, "Configuring “smart” quotes", &options);
+        assert_eq!(input.id(), "configuring-smart-quotes");
+    }
+
+    #[test]
+    fn truncated_ids() {
+        let options = Options {
+            max_id_length: Some(20),
+            ..basic_options()
+        };
+
+        let input = Input::new(ContentType::Concept, "Short title", &options);
+        assert_eq!(input.id(), "short-title");
+
+        let input = Input::new(ContentType::Concept, "Installing the product", &options);
+        assert_eq!(input.id(), "installing-the");
+
+        // A single long word is cut in the middle.
+        let input = Input::new(
+            ContentType::Concept,
+            "Supercalifragilisticexpialidocious",
+            &options,
+        );
+        assert_eq!(input.id(), "supercalifragilistic");
+
+        // The suffix that avoids a collision fits in the maximum length.
+        let mut input = Input::new(ContentType::Concept, "Installing the product", &options);
+        input.id_suffix = Some(2);
+        assert_eq!(input.id(), "installing-the-2");
+    }
+
+    #[test]
+    fn degenerate_final_ids() {
+        let options = Options {
+            id_fallback: IdFallback::Hash,
+            max_id_length: Some(3),
+            min_id_length: 3,
+            ..basic_options()
+        };
+
+        // The hash fallback is shortened to `id`, which is too short.
+        let module = Module::new(ContentType::Concept, "安装产品", &options);
+        let error = module
+            .check_id()
+            .expect_err("A degenerate ID passed the check");
+        assert!(error.to_string().contains("--max-id-length"));
+
+        // A one-character title is long enough by default.
+        let options = basic_options();
+        let module = Module::new(ContentType::Concept, "X", &options);
+        assert!(module.check_id().is_ok());
+
+        // A converted section doesn't accept the `--id` option.
+        let module: Module = Input::new(ContentType::Concept, "!!", &options)
+            .body("Text.")
+            .into();
+        let error = module
+            .check_id()
+            .expect_err("A degenerate ID passed the check");
+        assert!(error.to_string().contains("Rename the section"));
+        assert!(!error.to_string().contains("`--id` option"));
+
+        let module = Module::new(ContentType::Concept, "!!", &options);
+        let error = module
+            .check_id()
+            .expect_err("A degenerate ID passed the check");
+        assert!(error.to_string().contains("`--id` option"));
+    }
 }
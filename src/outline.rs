@@ -0,0 +1,452 @@
+/*
+newdoc: Generate pre-populated documentation modules formatted with AsciiDoc.
+Copyright (C) 2026  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! # `outline.rs`
+//!
+//! This module compares an outline of assemblies and modules with the files on the disk.
+//! The `plan` command reports the differences, and the `apply` command creates
+//! the missing files and adds the missing include statements to existing assemblies.
+//!
+//! The outline is a YAML list of entries, which can nest in assemblies:
+//!
+//! ```yaml
+//! - type: assembly
+//!   title: Installing the product
+//!   includes:
+//!     - type: concept
+//!       title: Installation overview
+//!     - type: procedure
+//!       title: Installing the product
+//! ```
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use serde::Deserialize;
+
+use crate::collisions::Existing;
+use crate::groups::Overrides;
+use crate::module::{ContentType, Input, Module};
+use crate::write::{self, Replaced};
+use crate::Options;
+
+/// A single entry in the outline file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    #[serde(rename = "type")]
+    mod_type: ContentType,
+    title: String,
+    /// The ID that replaces the ID derived from the title
+    id: Option<String>,
+    /// The file name that replaces the file name derived from the ID
+    file_name: Option<String>,
+    /// The entries that this assembly includes
+    #[serde(default)]
+    includes: Vec<Entry>,
+}
+
+/// A file that an assembly includes, as the outline defines it.
+#[derive(Debug, Clone)]
+struct Child {
+    file_name: String,
+    include_statement: String,
+}
+
+/// A file from the outline, compared with the state on the disk.
+#[derive(Debug)]
+struct Planned {
+    module: Module,
+    path: PathBuf,
+    exists: bool,
+    /// The files that this assembly includes according to the outline
+    children: Vec<Child>,
+    /// The files that this assembly should include, but the existing file doesn't
+    missing: Vec<Child>,
+}
+
+/// The comparison of the whole outline with the files on the disk.
+#[derive(Debug)]
+struct Plan {
+    files: Vec<Planned>,
+    /// Files in the target directories that the outline doesn't mention
+    extra: Vec<PathBuf>,
+}
+
+/// Report how the files on the disk differ from the outline, without changing anything.
+pub fn plan(outline: &Path, options: &Options) -> Result<()> {
+    let plan = Plan::new(outline, options)?;
+
+    for planned in &plan.files {
+        if planned.exists {
+            log::info!("‣ File exists: {}", planned.path.display());
+        } else {
+            log::info!("‣ File to create: {}", planned.path.display());
+        }
+        for child in &planned.missing {
+            log::info!("  Missing include: {}", child.include_statement);
+        }
+    }
+    for path in &plan.extra {
+        log::info!("‣ File not in the outline: {}", path.display());
+    }
+
+    plan.summarize();
+
+    Ok(())
+}
+
+/// Create the files that are missing from the disk and add the missing include statements
+/// to existing assemblies. Leaves all other content in existing files intact.
+pub fn apply(outline: &Path, options: &Options) -> Result<()> {
+    let plan = Plan::new(outline, options)?;
+    let mut new_modules = Vec::new();
+    // Existing assemblies with the missing include statements added
+    let mut updated_files: Vec<(&Planned, String)> = Vec::new();
+
+    for planned in &plan.files {
+        if !planned.exists {
+            new_modules.push(&planned.module);
+        } else if !planned.missing.is_empty() {
+            let text = fs::read_to_string(&planned.path)
+                .wrap_err_with(|| eyre!("Failed to read the `{}` file.", planned.path.display()))?;
+
+            let updated = insert_includes(&text, &planned.children);
+
+            if options.dry_run {
+                log::info!("‣ Includes to add: {}", planned.path.display());
+                for child in &planned.missing {
+                    log::info!("  {}", child.include_statement);
+                }
+                if options.show_text {
+                    println!("{updated}");
+                }
+            }
+            updated_files.push((planned, updated));
+        }
+    }
+    for path in &plan.extra {
+        log::info!(
+            "‣ File not in the outline, left unchanged: {}",
+            path.display()
+        );
+    }
+
+    // Write the new modules and the updated assemblies together.
+    // The dry run only previews the updated assemblies.
+    let replaced: Vec<Replaced> = updated_files
+        .iter()
+        .map(|(planned, text)| Replaced {
+            module: &planned.module,
+            path: planned.path.clone(),
+            text,
+        })
+        .collect();
+    let outcomes = write::write_modules(&new_modules, &replaced, options)?;
+
+    if !options.dry_run && !options.stdout {
+        for (planned, _) in &updated_files {
+            log::info!("‣ Includes added: {}", planned.path.display());
+            for child in &planned.missing {
+                log::info!("  {}", child.include_statement);
+            }
+        }
+    }
+
+    write::check_skipped(&outcomes)
+}
+
+impl Plan {
+    /// Load the outline and compare each of its entries with the disk.
+    fn new(outline: &Path, options: &Options) -> Result<Self> {
+        let text = fs::read_to_string(outline)
+            .wrap_err_with(|| eyre!("Failed to read the outline `{}`.", outline.display()))?;
+        let entries: Vec<Entry> = serde_norway::from_str(&text)
+            .wrap_err_with(|| eyre!("Failed to parse the outline `{}`.", outline.display()))?;
+
+        let mut existing = Existing::scan(options);
+        let mut files = Vec::new();
+        for entry in &entries {
+            plan_entry(entry, options, &mut existing, &mut files)?;
+        }
+
+        let extra = extra_files(&files)?;
+
+        Ok(Self { files, extra })
+    }
+
+    /// Print the number of changes that the plan represents.
+    fn summarize(&self) {
+        let to_create = self.files.iter().filter(|planned| !planned.exists).count();
+        let existing = self.files.len() - to_create;
+        let missing: usize = self.files.iter().map(|planned| planned.missing.len()).sum();
+
+        log::info!(
+            "Summary: {to_create} files to create, {existing} existing files, \
+            {missing} missing includes, {} files not in the outline.",
+            self.extra.len()
+        );
+    }
+}
+
+/// Prepare the module for this outline entry and for all the entries that it includes.
+/// Returns the entry as a child that the parent assembly includes.
+fn plan_entry(
+    entry: &Entry,
+    options: &Options,
+    existing: &mut Existing,
+    files: &mut Vec<Planned>,
+) -> Result<Child> {
+    if !entry.includes.is_empty() && entry.mod_type != ContentType::Assembly {
+        bail!(
+            "The {} `{}` includes other files in the outline, but only an assembly can include files.",
+            entry.mod_type,
+            entry.title
+        );
+    }
+
+    // The included modules must exist before the assembly so that it can use
+    // their include statements.
+    let children = entry
+        .includes
+        .iter()
+        .map(|child| plan_entry(child, options, existing, files))
+        .collect::<Result<Vec<Child>>>()?;
+
+    let overrides = Overrides {
+        id: entry.id.clone(),
+        file_name: entry.file_name.clone(),
+    };
+    overrides.check()?;
+    let mut input = overrides.apply(Input::new(entry.mod_type, &entry.title, options));
+    if !children.is_empty() {
+        input = input.include(
+            children
+                .iter()
+                .map(|child| child.include_statement.clone())
+                .collect(),
+        );
+    }
+    // Only a new file can collide with the existing content.
+    if !input.options.target_dir.join(input.file_name()).exists() {
+        input = existing.resolve(input, options)?;
+    }
+    let module: Module = input.into();
+
+    let child = Child {
+        file_name: module.file_name.clone(),
+        include_statement: module.include_statement.clone(),
+    };
+
+    let path = module.full_path();
+
+    // The outline can include the same module in several assemblies. Plan it only once.
+    if files.iter().any(|planned| planned.path == path) {
+        return Ok(child);
+    }
+
+    let exists = path.exists();
+
+    let missing = if exists && !children.is_empty() {
+        let text = fs::read_to_string(&path)
+            .wrap_err_with(|| eyre!("Failed to read the `{}` file.", path.display()))?;
+        let included: HashSet<&str> = text.lines().filter_map(included_file_name).collect();
+
+        children
+            .iter()
+            .filter(|child| !included.contains(child.file_name.as_str()))
+            .cloned()
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    files.push(Planned {
+        module,
+        path,
+        exists,
+        children,
+        missing,
+    });
+
+    Ok(child)
+}
+
+/// Find the AsciiDoc files in the target directories that the outline doesn't mention.
+fn extra_files(files: &[Planned]) -> Result<Vec<PathBuf>> {
+    let planned: HashSet<&Path> = files.iter().map(|planned| planned.path.as_path()).collect();
+    let directories: HashSet<&Path> = files
+        .iter()
+        .filter_map(|planned| planned.path.parent())
+        .collect();
+
+    let mut extra = Vec::new();
+
+    for directory in directories {
+        // The target directory might not exist yet if the outline is entirely new.
+        if !directory.is_dir() {
+            continue;
+        }
+
+        let entries = fs::read_dir(directory)
+            .wrap_err_with(|| eyre!("Failed to list the `{}` directory.", directory.display()))?;
+
+        for dir_entry in entries {
+            let path = dir_entry?.path();
+            let is_adoc = path
+                .extension()
+                .is_some_and(|extension| extension == "adoc");
+
+            if is_adoc && path.is_file() && !planned.contains(path.as_path()) {
+                extra.push(path);
+            }
+        }
+    }
+
+    extra.sort();
+
+    Ok(extra)
+}
+
+/// If the line is an include statement, return the file name that it includes.
+fn included_file_name(line: &str) -> Option<&str> {
+    let target = line.trim_start().strip_prefix("include::")?;
+    let target = target.split('[').next()?;
+
+    Path::new(target).file_name()?.to_str()
+}
+
+/// Add the include statements for the children that the assembly text doesn't include yet.
+/// Each new include goes after the include of the preceding child from the outline,
+/// or before the following child, so that the assembly keeps the order of the outline.
+/// All the existing lines stay unchanged.
+fn insert_includes(text: &str, children: &[Child]) -> String {
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+
+    // The line that includes this file name, if any
+    let find = |lines: &[String], file_name: &str| {
+        lines
+            .iter()
+            .position(|line| included_file_name(line) == Some(file_name))
+    };
+
+    for (index, child) in children.iter().enumerate() {
+        if find(&lines, &child.file_name).is_some() {
+            continue;
+        }
+
+        let preceding = children[..index]
+            .iter()
+            .rev()
+            .find_map(|sibling| find(&lines, &sibling.file_name));
+        let following = children[index + 1..]
+            .iter()
+            .find_map(|sibling| find(&lines, &sibling.file_name));
+
+        let include = child.include_statement.clone();
+
+        // The position of the new lines, and the new lines themselves,
+        // which separate the include from its neighbor with a blank line
+        let (position, new_lines) = if let Some(position) = preceding {
+            (position + 1, [String::new(), include])
+        } else if let Some(position) = following {
+            (position, [include, String::new()])
+        } else {
+            // No sibling is included yet. Add the include after the last include statement,
+            // or before the block that restores the context at the end of the assembly.
+            let last_include = lines
+                .iter()
+                .rposition(|line| included_file_name(line).is_some());
+            let context_restore = lines
+                .iter()
+                .position(|line| line.starts_with("ifdef::parent-context-of-"));
+
+            match (last_include, context_restore) {
+                (Some(position), _) => (position + 1, [String::new(), include]),
+                (None, Some(position)) => (position, [include, String::new()]),
+                (None, None) => (lines.len(), [String::new(), include]),
+            }
+        };
+
+        lines.splice(position..position, new_lines);
+    }
+
+    let mut updated = lines.join("\n");
+    if text.ends_with('\n') {
+        updated.push('\n');
+    }
+
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn child(file_name: &str) -> Child {
+        Child {
+            file_name: file_name.to_string(),
+            include_statement: format!("include::modules/{file_name}[leveloffset=+1]"),
+        }
+    }
+
+    #[test]
+    fn parse_outline() {
+        let yaml = "
+- type: assembly
+  title: An assembly
+  includes:
+    - type: concept
+      title: A concept
+- type: snippet
+  title: A snippet
+";
+        let entries: Vec<Entry> =
+            serde_norway::from_str(yaml).expect("Failed to parse the outline");
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].mod_type, ContentType::Assembly);
+        assert_eq!(entries[0].includes[0].title, "A concept");
+        assert!(entries[1].includes.is_empty());
+    }
+
+    #[test]
+    fn insert_missing_includes_in_order() {
+        let text = "= Assembly\n\nAuthored text.\n\n\
+            include::modules/con_a.adoc[leveloffset=+1]\n\n\
+            include::modules/proc_c.adoc[leveloffset=+1]\n\n\
+            == Next steps\n";
+        let children = [
+            child("con_a.adoc"),
+            child("con_b.adoc"),
+            child("proc_c.adoc"),
+            child("ref_d.adoc"),
+        ];
+
+        let expected = "= Assembly\n\nAuthored text.\n\n\
+            include::modules/con_a.adoc[leveloffset=+1]\n\n\
+            include::modules/con_b.adoc[leveloffset=+1]\n\n\
+            include::modules/proc_c.adoc[leveloffset=+1]\n\n\
+            include::modules/ref_d.adoc[leveloffset=+1]\n\n\
+            == Next steps\n";
+
+        assert_eq!(insert_includes(text, &children), expected);
+    }
+}
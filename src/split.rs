@@ -0,0 +1,361 @@
+/*
+newdoc: Generate pre-populated documentation modules formatted with AsciiDoc.
+Copyright (C) 2026  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! # `split.rs`
+//!
+//! This module splits a monolithic AsciiDoc document into modules.
+//! Each top-level section becomes a module, and the original file becomes
+//! an assembly that includes them.
+
+use std::fs;
+use std::path::Path;
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use regex::Regex;
+
+use crate::convert::{Document, Section};
+use crate::Options;
+use crate::REGEX_ERROR;
+
+/// Attributes that newdoc generates itself, so the split files shouldn't copy them.
+const GENERATED_ATTRIBUTES: [&str; 3] = [
+    ":_newdoc-version:",
+    ":_template-generated:",
+    ":_mod-docs-content-type:",
+];
+
+/// Split the AsciiDoc file into modules in the target directory
+/// and replace the file with an assembly that includes them.
+pub fn split(file: &Path, options: &Options, interactive: bool) -> Result<()> {
+    let text = fs::read_to_string(file)
+        .wrap_err_with(|| eyre!("Failed to read the `{}` file.", file.display()))?;
+
+    let document =
+        parse(&text).wrap_err_with(|| eyre!("Failed to split the `{}` file.", file.display()))?;
+
+    log::debug!(
+        "Splitting `{}` into {} modules.",
+        file.display(),
+        document.sections.len()
+    );
+
+    document.write(options, Some(file), interactive)
+}
+
+/// A line of the source document, as the parser understands it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line {
+    /// A section title at this level, where the document title is level 0
+    Heading(usize),
+    /// A block attribute line that sets an ID, such as `[id="..."]`
+    Id,
+    /// Any other line, including everything inside delimited blocks
+    Other,
+}
+
+/// Parse the AsciiDoc text into the document title and its top-level sections.
+fn parse(text: &str) -> Result<Document> {
+    let lines: Vec<&str> = text.lines().collect();
+    let kinds = classify(&lines);
+
+    let Some(title_index) = kinds.iter().position(|kind| *kind == Line::Heading(0)) else {
+        bail!("The document has no title. Add a level 0 title, such as `= My guide`.");
+    };
+
+    let headings: Vec<(usize, usize)> = kinds
+        .iter()
+        .enumerate()
+        .filter_map(|(index, kind)| match kind {
+            Line::Heading(level) => Some((index, *level)),
+            _ => None,
+        })
+        .collect();
+
+    if headings
+        .iter()
+        .any(|&(index, level)| level == 0 && index != title_index)
+    {
+        bail!("The document has more than one level 0 title. Split each part separately.");
+    }
+
+    // Split at the highest section level in the document.
+    let Some(split_level) = headings
+        .iter()
+        .map(|&(_, level)| level)
+        .filter(|level| *level > 0)
+        .min()
+    else {
+        bail!("The document has no sections to split into modules.");
+    };
+
+    // The start of each section, including the ID lines above its title
+    let starts: Vec<usize> = headings
+        .iter()
+        .filter(|&&(_, level)| level == split_level)
+        .map(|&(index, _)| attached_start(&kinds, index))
+        .collect();
+
+    let title_start = attached_start(&kinds, title_index);
+    let title_id = find_id(&lines[title_start..title_index]);
+    let title = heading_title(lines[title_index]);
+
+    // Keep the content before the title as well, except for the ID and the generated attributes.
+    let before_title = lines[..title_start]
+        .iter()
+        .filter(|line| !is_generated_attribute(line))
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n");
+    let after_title = lines[title_index + 1..starts[0]].join("\n");
+    let preamble = [before_title.trim(), after_title.trim()].join("\n\n");
+
+    let mut sections = Vec::new();
+
+    for (position, &start) in starts.iter().enumerate() {
+        let end = starts.get(position + 1).copied().unwrap_or(lines.len());
+        let heading_index = (start..end)
+            .find(|index| kinds[*index] == Line::Heading(split_level))
+            .ok_or_else(|| eyre!("A section lost its title while splitting."))?;
+
+        // Shift the subsection headings so that the section title becomes level 0.
+        let body = (heading_index + 1..end)
+            .map(|index| match kinds[index] {
+                Line::Heading(level) => format!(
+                    "{} {}",
+                    "=".repeat(level - split_level + 1),
+                    heading_title(lines[index])
+                ),
+                _ => lines[index].to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        sections.push(Section {
+            title: heading_title(lines[heading_index]),
+            id: find_id(&lines[start..heading_index]),
+            mod_type: None,
+            body,
+        });
+    }
+
+    Ok(Document {
+        title,
+        id: title_id,
+        preamble: preamble.trim().to_string(),
+        sections,
+    })
+}
+
+/// Recognize the headings and ID lines, skipping over the content of delimited blocks,
+/// where a line that looks like a heading is just literal content.
+fn classify(lines: &[&str]) -> Vec<Line> {
+    let delimiter = Regex::new(r"^(-{4,}|\.{4,}|={4,}|\*{4,}|_{4,}|/{4,}|\+{4,}|[|!,:]===|--)$")
+        .expect(REGEX_ERROR);
+    let heading = Regex::new(r"^=+\s+\S").expect(REGEX_ERROR);
+
+    let mut open_block: Option<&str> = None;
+    let mut kinds = Vec::with_capacity(lines.len());
+
+    for line in lines {
+        let line = line.trim_end();
+
+        let kind = if let Some(block) = open_block {
+            if line == block {
+                open_block = None;
+            }
+            Line::Other
+        } else if delimiter.is_match(line) {
+            open_block = Some(line);
+            Line::Other
+        } else if heading.is_match(line) {
+            let level = line.chars().take_while(|c| *c == '=').count() - 1;
+            Line::Heading(level)
+        } else if parse_id(line).is_some() {
+            Line::Id
+        } else {
+            Line::Other
+        };
+
+        kinds.push(kind);
+    }
+
+    kinds
+}
+
+/// Find the first line of the ID block attributes directly above the heading.
+fn attached_start(kinds: &[Line], heading_index: usize) -> usize {
+    let mut start = heading_index;
+    while start > 0 && kinds[start - 1] == Line::Id {
+        start -= 1;
+    }
+    start
+}
+
+/// Find the ID in the block attribute lines above a heading.
+fn find_id(lines: &[&str]) -> Option<String> {
+    lines.iter().find_map(|line| parse_id(line))
+}
+
+/// Read the ID from an AsciiDoc block attribute line, such as `[id="my-id_{context}"]`,
+/// `[[my-id]]`, or `[#my-id]`. The generated modules add their own context,
+/// so the ID drops the `_{context}` suffix.
+fn parse_id(line: &str) -> Option<String> {
+    let line = line.trim();
+
+    let id = if let Some(anchor) = line.strip_prefix("[[").and_then(|l| l.strip_suffix("]]")) {
+        anchor.split(',').next()?
+    } else if let Some(anchor) = line.strip_prefix("[#").and_then(|l| l.strip_suffix(']')) {
+        anchor.split(['.', '%', ',']).next()?
+    } else if let Some(attributes) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+        &named_attribute(attributes, "id")?
+    } else {
+        return None;
+    };
+
+    let id = id.trim().trim_end_matches("_{context}");
+
+    if id.is_empty() {
+        None
+    } else {
+        Some(id.to_string())
+    }
+}
+
+/// Find the value of the first attribute with this name in an AsciiDoc attribute list,
+/// such as `id="my-id", role="_abstract"`. Commas inside quoted values don't separate
+/// the attributes, and the quotes around the value aren't part of it.
+fn named_attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut entries: Vec<String> = vec![String::new()];
+    let mut quote: Option<char> = None;
+
+    for c in attributes.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, ',') => {
+                entries.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        if let Some(entry) = entries.last_mut() {
+            entry.push(c);
+        }
+    }
+
+    entries.iter().find_map(|entry| {
+        let (key, value) = entry.split_once('=')?;
+        if key.trim() != name {
+            return None;
+        }
+        let value = value.trim();
+        let unquoted = ['"', '\''].into_iter().find_map(|quote| {
+            value
+                .strip_prefix(quote)
+                .and_then(|value| value.strip_suffix(quote))
+        });
+        Some(unquoted.unwrap_or(value).to_string())
+    })
+}
+
+/// The text of the heading without the leading equal signs.
+fn heading_title(line: &str) -> String {
+    line.trim_start_matches('=').trim().to_string()
+}
+
+/// Check if the line is one of the attributes that newdoc generates.
+fn is_generated_attribute(line: &str) -> bool {
+    GENERATED_ATTRIBUTES
+        .iter()
+        .any(|attribute| line.starts_with(attribute))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUIDE: &str = r#":_mod-docs-content-type: ASSEMBLY
+[id="my-guide"]
+= My guide
+
+An introduction.
+
+[id="about-the-product_{context}"]
+== About the product
+
+The product does things.
+
+=== Details
+
+----
+== Not a heading
+----
+
+[[installing]]
+== Installing the product
+
+. Download it.
+. Install it.
+"#;
+
+    #[test]
+    fn split_sections() {
+        let document = parse(GUIDE).expect("Failed to parse a valid document");
+
+        assert_eq!(document.title, "My guide");
+        assert_eq!(document.id.as_deref(), Some("my-guide"));
+        assert_eq!(document.preamble, "An introduction.");
+        assert_eq!(document.sections.len(), 2);
+
+        let about = &document.sections[0];
+        assert_eq!(about.title, "About the product");
+        assert_eq!(about.id.as_deref(), Some("about-the-product"));
+        assert!(about.body.contains("\n== Details\n"));
+        assert!(about.body.contains("\n== Not a heading\n"));
+
+        let installing = &document.sections[1];
+        assert_eq!(installing.title, "Installing the product");
+        assert_eq!(installing.id.as_deref(), Some("installing"));
+        assert!(installing.body.contains(". Install it."));
+    }
+
+    #[test]
+    fn reject_unsplittable_documents() {
+        assert!(parse("Just a paragraph.\n").is_err());
+        assert!(parse("= Title\n\nNo sections here.\n").is_err());
+    }
+
+    #[test]
+    fn id_syntax() {
+        assert_eq!(
+            parse_id(r#"[id="an-id_{context}"]"#).as_deref(),
+            Some("an-id")
+        );
+        assert_eq!(parse_id("[[an-id,A label]]").as_deref(), Some("an-id"));
+        assert_eq!(parse_id("[#an-id.role]").as_deref(), Some("an-id"));
+        assert_eq!(parse_id("[role=\"_abstract\"]"), None);
+        assert_eq!(
+            parse_id(r#"[id="an-id", role="y"]"#).as_deref(),
+            Some("an-id")
+        );
+        assert_eq!(
+            parse_id(r#"[role="a,b", id='an-id_{context}']"#).as_deref(),
+            Some("an-id")
+        );
+    }
+}
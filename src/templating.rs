@@ -95,6 +95,26 @@ struct SnippetTemplate<'a> {
     // simplified: bool,
 }
 
+/// The template for content converted from an existing document.
+/// It adds the newdoc header, the ID, and the title around the converted body.
+// The template needs these bools for its conditions.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Template)]
+#[template(path = "converted.adoc", escape = "none")]
+struct ConvertedTemplate<'a> {
+    module_anchor: &'a str,
+    module_title: &'a str,
+    content_type: &'a str,
+    is_assembly: bool,
+    is_snippet: bool,
+    body: &'a str,
+    include_statements: &'a str,
+    metadata: bool,
+    generator_version: &'a str,
+    current_day: &'a str,
+    simplified: bool,
+}
+
 // We're implementing the template functions on the Input struct, not on Module,
 // because the templating happens at the point when newdoc composes the text of the module,
 // which is part of the module creation. The module then stores the rendered template.
@@ -116,13 +136,62 @@ impl Input {
         }
     }
 
+    /// Render the module around its converted body, without the template placeholders.
+    /// Only the generated lines around the body lose their excess blank lines.
+    fn converted_text(&self, body: &str) -> String {
+        const BODY_MARKER: &str = "<<newdoc-converted-body>>";
+
+        let include_statements = match &self.includes {
+            Some(include_statements) => include_statements.join("\n\n"),
+            None => String::new(),
+        };
+
+        let mut document = ConvertedTemplate {
+            module_anchor: &self.anchor(),
+            module_title: &self.title,
+            content_type: &self.mod_type.to_string().to_uppercase(),
+            is_assembly: self.mod_type == ContentType::Assembly,
+            is_snippet: self.mod_type == ContentType::Snippet,
+            body: BODY_MARKER,
+            include_statements: &include_statements,
+            metadata: self.options.metadata,
+            generator_version: generator_version(),
+            current_day: &current_day(),
+            simplified: self.options.simplified,
+        }
+        .render()
+        .expect("Failed to construct the document from the template");
+
+        while document.contains("\n\n\n") {
+            document = document.replace("\n\n\n", "\n\n");
+        }
+
+        // An empty body, such as an assembly without a preamble, leaves no blank lines behind.
+        let document = if body.trim().is_empty() {
+            document.replace(&format!("{BODY_MARKER}\n\n"), "")
+        } else {
+            document.replace(BODY_MARKER, body.trim())
+        };
+
+        document.trim().to_string() + "\n\n"
+    }
+
     /// Perform string replacements in the modular template that matches the `ContentType`.
     /// Return the template text with all replacements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the template fails to render, which indicates a bug in the template.
     #[must_use]
     pub fn text(&self) -> String {
         let generator_version = generator_version();
         let current_day = current_day();
 
+        // Converted content keeps its own comments and blank lines.
+        if let Some(body) = &self.body {
+            return self.converted_text(body);
+        }
+
         let mut document = match self.mod_type {
             ContentType::Assembly => AssemblyTemplate {
                 module_anchor: &self.anchor(),
@@ -0,0 +1,351 @@
+/*
+newdoc: Generate pre-populated documentation modules formatted with AsciiDoc.
+Copyright (C) 2026  Marek Suchánek  <msuchane@redhat.com>
+
+This program is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+This program is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! # `transaction.rs`
+//!
+//! This module writes a batch of files all at once, or not at all.
+//! It first writes every file to a temporary file next to its target,
+//! and only then renames the temporary files into place. If a step fails,
+//! it restores the files that existed before.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+
+/// A file that is ready to be written as part of a batch.
+#[derive(Debug)]
+pub struct StagedFile<'a> {
+    /// The final path of the file
+    pub path: PathBuf,
+    /// The new content of the file
+    pub text: &'a str,
+    /// If the file exists, keep the previous version at this path.
+    /// Otherwise, the previous version is removed after a successful write.
+    pub backup: Option<PathBuf>,
+}
+
+/// Write all the files, or none of them. If `create_dirs` is true, create the missing
+/// directories for the files as well.
+///
+/// # Errors
+///
+/// Fails if two files share the same path, if a directory is missing and `create_dirs` is false,
+/// or if any of the files cannot be written.
+/// In that case, the files and directories on the disk stay as they were before.
+pub fn commit(files: &[StagedFile], create_dirs: bool) -> Result<()> {
+    let mut paths = HashSet::new();
+    for file in files {
+        if !paths.insert(&file.path) {
+            bail!(
+                "Several generated files have the same path: {}",
+                file.path.display()
+            );
+        }
+    }
+
+    let created = create_missing_dirs(files, create_dirs)?;
+
+    // Write the content next to the targets first. This checks that all the targets
+    // are writable before any existing file changes.
+    let mut temporary = Vec::with_capacity(files.len());
+    for file in files {
+        let temp = sibling(&file.path, "newdoc-tmp");
+        if let Err(error) = fs::write(&temp, file.text) {
+            remove_all(&temporary);
+            remove_dirs(&created);
+            return Err(error)
+                .wrap_err_with(|| eyre!("Failed to write the `{}` file.", file.path.display()));
+        }
+        temporary.push(temp);
+    }
+
+    // Move the new files into place, one by one, and remember the previous versions.
+    let mut replaced: Vec<(&StagedFile, Option<PathBuf>)> = Vec::with_capacity(files.len());
+    for (position, file) in files.iter().enumerate() {
+        match replace(file, &temporary[position]) {
+            Ok(previous) => replaced.push((file, previous)),
+            Err(error) => {
+                log::error!("Restoring the previous state of the files.");
+                remove_all(&temporary[position..]);
+                restore(&replaced);
+                remove_dirs(&created);
+                return Err(error);
+            }
+        }
+    }
+
+    for dir in &created {
+        log::info!("‣ Directory created: {}", dir.display());
+    }
+
+    // Everything succeeded. Only the backups that the user asked for remain.
+    for (file, previous) in &replaced {
+        if let (Some(previous), None) = (previous, &file.backup) {
+            if let Err(error) = fs::remove_file(previous) {
+                log::warn!(
+                    "Failed to remove the temporary file `{}`: {error}",
+                    previous.display()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Find the directories that the files need, but that don't exist.
+/// Either create them, from the outermost to the innermost, or fail.
+/// Returns the created directories.
+fn create_missing_dirs(files: &[StagedFile], create_dirs: bool) -> Result<Vec<PathBuf>> {
+    let mut missing: Vec<&Path> = Vec::new();
+    for file in files {
+        let ancestors = file.path.ancestors().skip(1);
+        for dir in ancestors.take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists()) {
+            if !missing.contains(&dir) {
+                missing.push(dir);
+            }
+        }
+    }
+
+    if missing.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Shorter paths are the outer directories.
+    missing.sort_by_key(|dir| dir.components().count());
+
+    if !create_dirs {
+        bail!(
+            "The `{}` directory doesn't exist. Create it, or add the --create-dirs option.",
+            missing[0].display()
+        );
+    }
+
+    let mut created = Vec::with_capacity(missing.len());
+    for dir in missing {
+        if let Err(error) = fs::create_dir(dir) {
+            remove_dirs(&created);
+            return Err(error)
+                .wrap_err_with(|| eyre!("Failed to create the `{}` directory.", dir.display()));
+        }
+        created.push(dir.to_path_buf());
+    }
+
+    Ok(created)
+}
+
+/// Remove the directories that this batch created, from the innermost to the outermost.
+fn remove_dirs(created: &[PathBuf]) {
+    for dir in created.iter().rev() {
+        if let Err(error) = fs::remove_dir(dir) {
+            log::error!(
+                "Failed to remove the `{}` directory: {error}",
+                dir.display()
+            );
+        }
+    }
+}
+
+/// Move the temporary file to the final path. If a file exists there,
+/// move it aside first and return its new location.
+fn replace(file: &StagedFile, temp: &Path) -> Result<Option<PathBuf>> {
+    if file.path.is_dir() {
+        bail!(
+            "Failed to write the `{}` file. A directory exists at the path.",
+            file.path.display()
+        );
+    }
+
+    let previous = if file.path.exists() {
+        let aside = file
+            .backup
+            .clone()
+            .unwrap_or_else(|| sibling(&file.path, "newdoc-old"));
+        fs::rename(&file.path, &aside)
+            .wrap_err_with(|| eyre!("Failed to back up the `{}` file.", file.path.display()))?;
+        Some(aside)
+    } else {
+        None
+    };
+
+    if let Err(error) = fs::rename(temp, &file.path) {
+        if let Some(aside) = &previous {
+            // Best effort: the caller reports the original error.
+            let _ = fs::rename(aside, &file.path);
+        }
+        return Err(error)
+            .wrap_err_with(|| eyre!("Failed to write the `{}` file.", file.path.display()));
+    }
+
+    Ok(previous)
+}
+
+/// Undo the replaced files in the reverse order, and put the previous versions back.
+fn restore(replaced: &[(&StagedFile, Option<PathBuf>)]) {
+    for (file, previous) in replaced.iter().rev() {
+        let result = match previous {
+            Some(previous) => fs::rename(previous, &file.path),
+            None => fs::remove_file(&file.path),
+        };
+        if let Err(error) = result {
+            log::error!("Failed to restore `{}`: {error}", file.path.display());
+        }
+    }
+}
+
+/// Remove the temporary files, ignoring any that are already gone.
+fn remove_all(paths: &[PathBuf]) {
+    for path in paths {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// A hidden file next to `path`, such as `.con_my-module.adoc.newdoc-tmp`.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.{suffix}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The names of the entries in the directory, sorted.
+    fn entries(directory: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(directory)
+            .expect("Failed to list a directory")
+            .map(|entry| {
+                entry
+                    .expect("Failed to read a directory entry")
+                    .file_name()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn nothing_written_on_failure() {
+        let directory = tempfile::tempdir().expect("Failed to create a temporary directory");
+        let directory = directory.path();
+        let existing = directory.join("con_existing.adoc");
+        fs::write(&existing, "Old content").expect("Failed to write a file");
+
+        let files = [
+            StagedFile {
+                path: existing.clone(),
+                text: "New content",
+                backup: None,
+            },
+            StagedFile {
+                path: directory.join("con_new.adoc"),
+                text: "New content",
+                backup: None,
+            },
+            StagedFile {
+                path: directory.join("missing-directory/con_unwritable.adoc"),
+                text: "New content",
+                backup: None,
+            },
+        ];
+
+        assert!(commit(&files, false).is_err());
+        assert_eq!(
+            fs::read_to_string(&existing).expect("Failed to read a file"),
+            "Old content"
+        );
+        assert_eq!(entries(directory), ["con_existing.adoc"]);
+
+        // Without the unwritable file, the batch succeeds.
+        commit(&files[..2], false).expect("Failed to write a valid batch");
+        assert_eq!(
+            fs::read_to_string(&existing).expect("Failed to read a file"),
+            "New content"
+        );
+        assert_eq!(entries(directory), ["con_existing.adoc", "con_new.adoc"]);
+    }
+
+    #[test]
+    fn restore_after_failed_rename() {
+        let directory = tempfile::tempdir().expect("Failed to create a temporary directory");
+        let directory = directory.path();
+        let existing = directory.join("con_existing.adoc");
+        fs::write(&existing, "Old content").expect("Failed to write a file");
+        // The temporary file is writable, but it can't replace a directory.
+        fs::create_dir(directory.join("con_directory.adoc")).expect("Failed to create a directory");
+
+        let files = [
+            StagedFile {
+                path: existing.clone(),
+                text: "New content",
+                backup: None,
+            },
+            StagedFile {
+                path: directory.join("topic/con_new.adoc"),
+                text: "New content",
+                backup: None,
+            },
+            StagedFile {
+                path: directory.join("con_directory.adoc"),
+                text: "New content",
+                backup: None,
+            },
+        ];
+
+        let error = commit(&files, true).expect_err("Replacing a directory didn't fail");
+        assert!(error
+            .to_string()
+            .contains("A directory exists at the path."));
+
+        assert_eq!(
+            fs::read_to_string(&existing).expect("Failed to read a file"),
+            "Old content"
+        );
+        assert!(directory.join("con_directory.adoc").is_dir());
+        // No `.newdoc-old` or `.newdoc-tmp` files, and no `topic` directory remain.
+        assert_eq!(
+            entries(directory),
+            ["con_directory.adoc", "con_existing.adoc"]
+        );
+    }
+
+    #[test]
+    fn create_directories() {
+        let temp = tempfile::tempdir().expect("Failed to create a temporary directory");
+        let directory = temp.path().join("docs");
+        let nested = directory.join("modules/topic");
+        let files = [StagedFile {
+            path: nested.join("con_new.adoc"),
+            text: "New content",
+            backup: None,
+        }];
+
+        assert!(commit(&files, false).is_err());
+        assert!(!directory.exists());
+
+        commit(&files, true).expect("Failed to create the directories");
+        assert!(nested.join("con_new.adoc").is_file());
+    }
+}
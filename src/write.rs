@@ -17,53 +17,665 @@ along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
 use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
-use color_eyre::eyre::{eyre, Result, WrapErr};
-use dialoguer::{theme::ColorfulTheme, Confirm};
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use console::Style;
+use dialoguer::{theme::ColorfulTheme, Select};
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
 
-use crate::module::Module;
+use crate::cmd_line::{OutputFormat, Overwrite};
+use crate::editor;
+use crate::git;
+use crate::module::{ContentType, Module};
+use crate::transaction::{self, StagedFile};
 use crate::Options;
 
+/// What happened to a single generated file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WriteOutcome {
+    /// The file didn't exist and newdoc created it.
+    Written,
+    /// The file existed and newdoc replaced it, possibly after backing it up.
+    Overwritten,
+    /// The file existed and the overwrite policy preserved it.
+    Skipped,
+    /// The file existed and the user chose to preserve it at the prompt.
+    Declined,
+    /// The file existed and the user chose to save the new version next to it.
+    Alongside,
+    /// The dry-run mode only reported the file and wrote nothing.
+    Previewed,
+    /// The text went to the standard output instead of the file.
+    Printed,
+}
+
+/// What newdoc does with a module file, decided before it writes anything.
+#[derive(Debug)]
+enum Decision {
+    /// Create a new file.
+    Write,
+    /// Replace the existing file, and keep its previous version at the backup path if set.
+    Overwrite(Option<PathBuf>),
+    /// Leave the existing file alone and write the new version to this path instead.
+    Alongside(PathBuf),
+    /// Leave the existing file alone.
+    Keep(WriteOutcome),
+}
+
+/// An existing file that newdoc rewrites as part of a batch, such as a document
+/// that newdoc converts to an assembly. The module describes the file in the JSON report.
+pub struct Replaced<'a> {
+    pub module: &'a Module,
+    pub path: PathBuf,
+    pub text: &'a str,
+}
+
+/// The machine-readable record about a generated file in the JSON report.
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    content_type: ContentType,
+    title: &'a str,
+    anchor: &'a str,
+    file_name: &'a str,
+    path: PathBuf,
+    include_statement: &'a str,
+    status: WriteOutcome,
+}
+
 impl Module {
     /// Write the generated module content to the path specified in `options` with the set file name.
-    pub fn write_file(&self, options: &Options) -> Result<()> {
-        // Compose the full (but still relative) file path from the target directory and the file name
-        let full_path_buf = &options.target_dir.join(&self.file_name);
-        let full_path = full_path_buf.as_path();
+    /// If the file already exists, follow the overwrite policy in `options`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be written or if the overwrite prompt fails.
+    pub fn write_file(&self, options: &Options) -> Result<WriteOutcome> {
+        let outcomes = write_modules(&[self], &[], options)?;
+        Ok(outcomes[0])
+    }
 
-        log::debug!("Writing file `{}`", &full_path.display());
+    /// Decide what happens to the file according to the overwrite policy.
+    /// This might ask the user, but it doesn't change any files.
+    fn decide(&self, options: &Options) -> Result<Decision> {
+        let full_path = self.full_path();
 
-        // If the target file already exists, just print out an error
-        if full_path.exists() {
-            log::warn!("File already exists: {}", full_path.display());
+        // If the target file doesn't exist, there's nothing to decide
+        if !full_path.exists() {
+            return Ok(Decision::Write);
+        }
+
+        log::warn!("File already exists: {}", full_path.display());
+
+        let decision = match options.overwrite {
+            // Asking requires a terminal. Scripts and CI get the safe behavior instead.
+            Overwrite::Ask if !std::io::stdin().is_terminal() => {
+                log::warn!("→ Preserving the existing file, because the input is not a terminal.");
+                Decision::Keep(WriteOutcome::Skipped)
+            }
+            Overwrite::Ask => self.ask(&full_path)?,
+            Overwrite::Never => {
+                log::info!("→ Preserving the existing file.");
+                Decision::Keep(WriteOutcome::Skipped)
+            }
+            Overwrite::Always => {
+                log::warn!("→ Rewriting the file.");
+                Decision::Overwrite(None)
+            }
+            Overwrite::Backup => {
+                let backup = backup_path(&full_path);
+                log::warn!("→ Rewriting the file. Backup: {}", backup.display());
+                Decision::Overwrite(Some(backup))
+            }
+        };
 
+        Ok(decision)
+    }
+
+    /// Ask the user what to do with the existing file. The user can look at the differences
+    /// between the files before deciding.
+    fn ask(&self, full_path: &Path) -> Result<Decision> {
+        let alongside = alongside_path(full_path);
+        let choices = [
+            "No, preserve the existing file".to_string(),
+            "Yes, overwrite it".to_string(),
+            "Show the differences".to_string(),
+            format!(
+                "Write the new version alongside, as {}",
+                alongside.display()
+            ),
+        ];
+
+        loop {
             // A prompt enabling the user to overwrite the existing file
-            let overwrite = Confirm::with_theme(&ColorfulTheme::default())
+            let selection = Select::with_theme(&ColorfulTheme::default())
                 .with_prompt("Do you want to overwrite it?")
-                .wait_for_newline(true)
-                // The default selection is "false", that is, don't overwrite the file.
-                .default(false)
+                .items(&choices)
+                // The default selection is to preserve the file.
+                .default(0)
                 .interact()?;
 
-            if overwrite {
-                log::warn!("→ Rewriting the file.");
+            match selection {
+                0 => {
+                    log::info!("→ Preserving the existing file.");
+                    return Ok(Decision::Keep(WriteOutcome::Declined));
+                }
+                1 => {
+                    log::warn!("→ Rewriting the file.");
+                    return Ok(Decision::Overwrite(None));
+                }
+                2 => {
+                    let existing = fs::read_to_string(full_path).wrap_err_with(|| {
+                        eyre!("Failed to read the `{}` file.", full_path.display())
+                    })?;
+                    print_diff(full_path, &existing, &self.text);
+                    // Ask again, now that the user knows the differences.
+                }
+                _ => {
+                    log::info!("→ Writing the new version alongside the existing file.");
+                    return Ok(Decision::Alongside(alongside));
+                }
+            }
+        }
+    }
+
+    /// Report the file that `write_file` would write, and what would happen to an existing file.
+    fn preview(&self, full_path: &Path, options: &Options) {
+        log::info!("‣ File to generate: {}", full_path.display());
+        log::info!("  ID: {}", self.anchor());
+        log::info!("  {}", self.include_statement);
+
+        if let Some(dir) = full_path.parent().filter(|dir| !dir.exists()) {
+            if options.create_dirs {
+                log::info!("  newdoc would create the {} directory.", dir.display());
             } else {
-                log::info!("→ Preserving the existing file.");
-                // Break from generating this particular module.
-                // Other modules that might be in the queue will be generated on next iteration.
-                return Ok(());
+                log::warn!(
+                    "  The {} directory doesn't exist. Add the --create-dirs option to create it.",
+                    dir.display()
+                );
             }
         }
 
-        // If the target file doesn't exist, try to write to it
-        fs::write(full_path, &self.text)
-            .wrap_err_with(|| eyre!("Failed to write the `{}` file.", &full_path.display()))?;
+        if full_path.exists() {
+            let action = match options.overwrite {
+                Overwrite::Ask if !std::io::stdin().is_terminal() => {
+                    "preserve it, because the input is not a terminal".to_string()
+                }
+                Overwrite::Ask => "ask before overwriting it".to_string(),
+                Overwrite::Never => "preserve it".to_string(),
+                Overwrite::Always => "overwrite it".to_string(),
+                Overwrite::Backup => format!(
+                    "overwrite it after a backup to {}",
+                    backup_path(full_path).display()
+                ),
+            };
+            log::warn!("  The file already exists. newdoc would {action}.");
+        }
 
-        // If the write succeeds, print the include statement
-        log::debug!("Successfully written file `{}`", &full_path.display());
-        log::info!("‣ File generated: {}", full_path.display());
-        log::info!("  {}", self.include_statement);
+        if options.show_text {
+            println!("{}", self.text);
+        }
+    }
+}
+
+/// Write the modules to their files, or print them to the standard output
+/// if the `stdout` option is set.
+///
+/// The modules are written all at once, or not at all. The `replaced` files,
+/// such as a document converted to an assembly, are overwritten as part of
+/// the same batch, regardless of the overwrite policy.
+///
+/// # Errors
+///
+/// Fails if any of the files cannot be written. In that case, newdoc writes none of them.
+pub fn write_modules(
+    modules: &[&Module],
+    replaced: &[Replaced],
+    options: &Options,
+) -> Result<Vec<WriteOutcome>> {
+    // Never write a file with a degenerate ID, such as `con_.adoc`.
+    for module in modules {
+        module.check_id()?;
+    }
+
+    let outcomes = if options.dry_run {
+        for module in modules {
+            module.preview(&module.full_path(), options);
+        }
+        vec![WriteOutcome::Previewed; modules.len()]
+    } else if options.stdout {
+        let files: Vec<(PathBuf, &str)> = modules
+            .iter()
+            .map(|module| (module.full_path(), module.text.as_str()))
+            .chain(replaced.iter().map(|file| (file.path.clone(), file.text)))
+            .collect();
+        print_files(&files);
+
+        vec![WriteOutcome::Printed; modules.len()]
+    } else {
+        let written = write_batch(modules, replaced, options)?;
+        let outcomes: Vec<WriteOutcome> = written.iter().map(|(outcome, _)| *outcome).collect();
+        if options.edit {
+            edit_written(modules, &written, options)?;
+        }
+        if options.git_add {
+            let mut paths: Vec<PathBuf> = modules
+                .iter()
+                .zip(&outcomes)
+                .filter(|(_, outcome)| {
+                    matches!(outcome, WriteOutcome::Written | WriteOutcome::Overwritten)
+                })
+                .map(|(module, _)| module.full_path())
+                .collect();
+            paths.extend(replaced.iter().map(|file| file.path.clone()));
+            git::add_and_commit(&paths, options)?;
+        }
+        outcomes
+    };
+
+    if options.output == OutputFormat::Json {
+        // The replaced files are either rewritten, or only previewed or printed.
+        let replaced_outcome = if options.dry_run {
+            WriteOutcome::Previewed
+        } else if options.stdout {
+            WriteOutcome::Printed
+        } else {
+            WriteOutcome::Overwritten
+        };
+        print_report(modules, &outcomes, replaced, replaced_outcome)?;
+    }
+
+    Ok(outcomes)
+}
+
+/// Check all the modules against the existing files first, and then write them together.
+/// Returns what happened to each module, and the path where its text went.
+fn write_batch(
+    modules: &[&Module],
+    replaced: &[Replaced],
+    options: &Options,
+) -> Result<Vec<(WriteOutcome, PathBuf)>> {
+    let decisions = modules
+        .iter()
+        .map(|module| module.decide(options))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut staged = Vec::new();
+    for (module, decision) in modules.iter().zip(&decisions) {
+        let (path, backup) = match decision {
+            Decision::Write | Decision::Overwrite(None) => (module.full_path(), None),
+            Decision::Overwrite(Some(backup)) => (module.full_path(), Some(backup.clone())),
+            Decision::Alongside(path) => (path.clone(), None),
+            Decision::Keep(_) => continue,
+        };
+        staged.push(StagedFile {
+            path,
+            text: &module.text,
+            backup,
+        });
+    }
+    staged.extend(replaced.iter().map(|file| StagedFile {
+        path: file.path.clone(),
+        text: file.text,
+        backup: None,
+    }));
+
+    log::debug!("Writing {} files", staged.len());
+    transaction::commit(&staged, options.create_dirs)?;
+
+    // If the writes succeed, print the include statements
+    let outcomes = modules
+        .iter()
+        .zip(decisions)
+        .map(|(module, decision)| match decision {
+            Decision::Keep(outcome) => (outcome, module.full_path()),
+            Decision::Alongside(path) => {
+                log::info!("‣ New version written alongside: {}", path.display());
+                (WriteOutcome::Alongside, path)
+            }
+            Decision::Write | Decision::Overwrite(_) => {
+                log::info!("‣ File generated: {}", module.full_path().display());
+                log::info!("  {}", module.include_statement);
+                let outcome = if matches!(decision, Decision::Write) {
+                    WriteOutcome::Written
+                } else {
+                    WriteOutcome::Overwritten
+                };
+                (outcome, module.full_path())
+            }
+        })
+        .collect();
+
+    Ok(outcomes)
+}
+
+/// Open the files that newdoc has just written in the editor.
+fn edit_written(
+    modules: &[&Module],
+    written: &[(WriteOutcome, PathBuf)],
+    options: &Options,
+) -> Result<()> {
+    let files: Vec<(PathBuf, &str)> = modules
+        .iter()
+        .zip(written)
+        .filter(|(_, (outcome, _))| {
+            matches!(
+                outcome,
+                WriteOutcome::Written | WriteOutcome::Overwritten | WriteOutcome::Alongside
+            )
+        })
+        .map(|(module, (_, path))| (path.clone(), module.text.as_str()))
+        .collect();
+
+    editor::open(&files, options)
+}
+
+/// Print the JSON report with a record for each module and what happened to its file,
+/// followed by the replaced files.
+fn print_report(
+    modules: &[&Module],
+    outcomes: &[WriteOutcome],
+    replaced: &[Replaced],
+    replaced_outcome: WriteOutcome,
+) -> Result<()> {
+    let records = records(modules, outcomes, replaced, replaced_outcome);
+
+    let json =
+        serde_json::to_string_pretty(&records).wrap_err("Failed to serialize the JSON report.")?;
+    println!("{json}");
+
+    Ok(())
+}
+
+/// The records of the JSON report for the modules and the replaced files.
+fn records<'a>(
+    modules: &[&'a Module],
+    outcomes: &[WriteOutcome],
+    replaced: &'a [Replaced],
+    replaced_outcome: WriteOutcome,
+) -> Vec<Record<'a>> {
+    let replaced = replaced.iter().map(|file| Record {
+        content_type: file.module.mod_type(),
+        title: file.module.title(),
+        anchor: file.module.anchor(),
+        file_name: file
+            .path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&file.module.file_name),
+        path: file.path.clone(),
+        include_statement: &file.module.include_statement,
+        status: replaced_outcome,
+    });
+
+    modules
+        .iter()
+        .zip(outcomes)
+        .map(|(module, outcome)| Record {
+            content_type: module.mod_type(),
+            title: module.title(),
+            anchor: module.anchor(),
+            file_name: &module.file_name,
+            path: module.full_path(),
+            include_statement: &module.include_statement,
+            status: *outcome,
+        })
+        .chain(replaced)
+        .collect()
+}
+
+/// Print the text of the files to the standard output. If there are several,
+/// a line such as `==> con_my-module.adoc <==` precedes each of them.
+pub fn print_files(files: &[(PathBuf, &str)]) {
+    let several = files.len() > 1;
+
+    // The generated text ends with a blank line, which separates it from the next file.
+    for (path, text) in files {
+        if several {
+            println!("==> {} <==", path.display());
+        }
+        print!("{text}");
+        log::info!("‣ File printed: {}", path.display());
+    }
+}
+
+/// Print a colored unified diff between the existing file and the new text.
+/// The diff goes to stderr, next to the prompt, so that it never mixes with the generated output.
+fn print_diff(path: &Path, existing: &str, new: &str) {
+    let diff = TextDiff::from_lines(existing, new);
+
+    eprintln!(
+        "{}",
+        Style::new()
+            .for_stderr()
+            .bold()
+            .apply_to(format!("--- {}", path.display()))
+    );
+    eprintln!(
+        "{}",
+        Style::new()
+            .for_stderr()
+            .bold()
+            .apply_to("+++ generated by newdoc")
+    );
+
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        eprintln!(
+            "{}",
+            Style::new().for_stderr().cyan().apply_to(hunk.header())
+        );
+        for change in hunk.iter_changes() {
+            let (sign, style) = match change.tag() {
+                ChangeTag::Delete => ("-", Style::new().for_stderr().red()),
+                ChangeTag::Insert => ("+", Style::new().for_stderr().green()),
+                ChangeTag::Equal => (" ", Style::new().for_stderr()),
+            };
+            eprint!("{}", style.apply_to(format!("{sign}{change}")));
+            if change.missing_newline() {
+                eprintln!();
+            }
+        }
+    }
+}
+
+/// Find an unused path for the new version if the user keeps the existing file,
+/// such as `file.adoc.new`, or `file.adoc.new.1` if that exists as well,
+/// so that an earlier version that the user hasn't merged yet stays intact.
+fn alongside_path(path: &Path) -> PathBuf {
+    unused_path(path, ".new")
+}
+
+/// Find an unused path for the backup of an existing file,
+/// such as `file.adoc.bak`, or `file.adoc.bak.1` if that exists as well.
+fn backup_path(path: &Path) -> PathBuf {
+    unused_path(path, ".bak")
+}
+
+/// Add the suffix to the path, and a number after it if the path with the suffix exists.
+fn unused_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut base = path.as_os_str().to_owned();
+    base.push(suffix);
+    let base = PathBuf::from(base);
+
+    let mut candidate = base.clone();
+    let mut number = 1;
+    while candidate.exists() {
+        let mut numbered = base.as_os_str().to_owned();
+        numbered.push(format!(".{number}"));
+        candidate = PathBuf::from(numbered);
+        number += 1;
+    }
+
+    candidate
+}
+
+/// Fail if the overwrite policy preserved any existing files,
+/// so that scripts can detect that some files weren't generated.
+/// Summarize the files that the user kept or saved alongside at the prompt.
+///
+/// # Errors
+///
+/// Fails if any of the outcomes is `WriteOutcome::Skipped`.
+pub fn check_skipped(outcomes: &[WriteOutcome]) -> Result<()> {
+    let count = |status: WriteOutcome| {
+        outcomes
+            .iter()
+            .filter(|outcome| **outcome == status)
+            .count()
+    };
+    let skipped = count(WriteOutcome::Skipped);
+    let declined = count(WriteOutcome::Declined);
+    let alongside = count(WriteOutcome::Alongside);
+
+    if declined > 0 {
+        log::info!("{declined} existing file(s) were preserved at the prompt and not generated.");
+    }
+    if alongside > 0 {
+        log::info!(
+            "{alongside} new file(s) were saved alongside the existing ones with the `.new` extension."
+        );
+    }
+
+    if skipped > 0 {
+        bail!(
+            "{skipped} existing file(s) were preserved and not generated. \
+            Set a different policy with the --overwrite option."
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbered_backups() {
+        let temp = tempfile::tempdir().expect("Failed to create a temporary directory");
+        let directory = temp.path().to_path_buf();
+        let file = directory.join("con_file.adoc");
+
+        assert_eq!(backup_path(&file), directory.join("con_file.adoc.bak"));
+
+        fs::write(directory.join("con_file.adoc.bak"), "").expect("Failed to write a file");
+        assert_eq!(backup_path(&file), directory.join("con_file.adoc.bak.1"));
+
+        assert_eq!(alongside_path(&file), directory.join("con_file.adoc.new"));
+        fs::write(directory.join("con_file.adoc.new"), "").expect("Failed to write a file");
+        assert_eq!(alongside_path(&file), directory.join("con_file.adoc.new.1"));
+    }
+
+    #[test]
+    fn dry_run_writes_nothing() {
+        let temp = tempfile::tempdir().expect("Failed to create a temporary directory");
+        let directory = temp.path().to_path_buf();
+        let options = Options {
+            target_dir: directory.clone(),
+            dry_run: true,
+            ..Default::default()
+        };
+
+        let module = Module::new(ContentType::Concept, "A previewed module", &options);
+        let outcome = module
+            .write_file(&options)
+            .expect("Failed to preview a module");
+
+        assert_eq!(outcome, WriteOutcome::Previewed);
+        assert!(!directory.join(&module.file_name).exists());
+    }
+
+    #[test]
+    fn degenerate_id_writes_nothing() {
+        let temp = tempfile::tempdir().expect("Failed to create a temporary directory");
+        let directory = temp.path().to_path_buf();
+        let options = Options {
+            target_dir: directory.clone(),
+            ..Default::default()
+        };
+
+        let module = Module::new(ContentType::Concept, "★★★", &options);
+        let error = module
+            .write_file(&options)
+            .expect_err("A file with a degenerate ID was written");
+
+        assert!(error.to_string().contains("--id-fallback"));
+        assert!(!directory.join("con_.adoc").exists());
+    }
+
+    #[test]
+    fn json_record() {
+        let options = Options::default();
+        let module = Module::new(ContentType::Procedure, "Installing the product", &options);
+        let record = Record {
+            content_type: module.mod_type(),
+            title: module.title(),
+            anchor: module.anchor(),
+            file_name: &module.file_name,
+            path: module.full_path(),
+            include_statement: &module.include_statement,
+            status: WriteOutcome::Written,
+        };
+
+        let json = serde_json::to_value(&record).expect("Failed to serialize a record");
+
+        assert_eq!(json["content_type"], "procedure");
+        assert_eq!(json["anchor"], "installing-the-product");
+        assert_eq!(json["file_name"], "proc_installing-the-product.adoc");
+        assert_eq!(json["status"], "written");
+    }
+
+    #[test]
+    fn never_overwrite_fails() {
+        let temp = tempfile::tempdir().expect("Failed to create a temporary directory");
+        let directory = temp.path().to_path_buf();
+        let options = Options {
+            target_dir: directory.clone(),
+            overwrite: Overwrite::Never,
+            ..Default::default()
+        };
+
+        let module = Module::new(ContentType::Concept, "An existing module", &options);
+        let path = directory.join(&module.file_name);
+        fs::write(&path, "Existing content").expect("Failed to write a file");
+
+        let outcomes = write_modules(&[&module], &[], &options).expect("Failed to write modules");
+        assert_eq!(outcomes, [WriteOutcome::Skipped]);
+        assert!(check_skipped(&outcomes).is_err());
+        assert_eq!(
+            fs::read_to_string(&path).expect("Failed to read a file"),
+            "Existing content"
+        );
+
+        // Files that the user kept or saved alongside at the prompt don't fail the run.
+        assert!(check_skipped(&[WriteOutcome::Declined, WriteOutcome::Alongside]).is_ok());
+    }
+
+    #[test]
+    fn json_records_include_replaced_files() {
+        let options = Options::default();
+        let module = Module::new(ContentType::Concept, "About the product", &options);
+        let assembly = Module::new(ContentType::Assembly, "My guide", &options);
+        let replaced = [Replaced {
+            module: &assembly,
+            path: PathBuf::from("docs/guide.adoc"),
+            text: &assembly.text,
+        }];
+
+        let records = records(
+            &[&module],
+            &[WriteOutcome::Written],
+            &replaced,
+            WriteOutcome::Overwritten,
+        );
 
-        Ok(())
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].content_type, ContentType::Assembly);
+        assert_eq!(records[1].file_name, "guide.adoc");
+        assert_eq!(records[1].path, PathBuf::from("docs/guide.adoc"));
+        assert_eq!(records[1].status, WriteOutcome::Overwritten);
     }
 }
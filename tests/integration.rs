@@ -5,7 +5,7 @@
 use std::path::PathBuf;
 use time::OffsetDateTime;
 
-use cmd_line::Verbosity;
+use cmd_line::{IdCollision, IdFallback, IdStyle, OutputFormat, Overwrite, Verbosity};
 use newdoc::*;
 
 // These values represent the default newdoc options.
@@ -128,6 +128,26 @@ fn minimal_options() -> Options {
         target_dir: PathBuf::from("."),
         verbosity: Verbosity::Default,
         simplified: true,
+        overwrite: Overwrite::Ask,
+        create_dirs: false,
+        id_style: IdStyle::Legacy,
+        id_collision: IdCollision::Error,
+        id_fallback: IdFallback::Error,
+        min_id_length: 1,
+        max_id_length: None,
+        id_substitutions: Vec::new(),
+        semantic_roles: Vec::new(),
+        target_dirs: Default::default(),
+        git_root: None,
+        topic: None,
+        editor: None,
+        edit: false,
+        git_add: false,
+        git_commit: None,
+        dry_run: false,
+        show_text: false,
+        stdout: false,
+        output: OutputFormat::Text,
     }
 }
 
@@ -210,3 +230,24 @@ fn test_minimal_snippet() {
 
     assert_eq!(snippet.text, pre_generated);
 }
+
+/// Test that a module with converted content keeps its ID and its body unchanged.
+#[test]
+fn test_converted_procedure() {
+    let mod_type = ContentType::Procedure;
+    let mod_title = "Installing the product";
+    let options = basic_options();
+    let body = "// Keep this comment.\n\n. Download the product.\n\n. Install it:\n+\n----\n$ install\n\n\n$ verify\n----\n";
+    let procedure: Module = Input::new(mod_type, mod_title, &options)
+        .with_id("installing")
+        .body(body)
+        .into();
+
+    let pre_generated = include_str!("./generated/proc_installing.adoc");
+    // Replace the version and date placeholders:
+    let pre_generated = pre_generated.replace("{{generator_version}}", generator_version());
+    let pre_generated = pre_generated.replace("{{current_day}}", &current_day());
+
+    assert_eq!(procedure.file_name, "proc_installing.adoc");
+    assert_eq!(procedure.text, pre_generated);
+}